@@ -0,0 +1,141 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::scanner::Match;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub number: u32,
+    pub digit: u32,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl<'a> Token<'a> {
+    pub fn new(line: &'a str, m: &Match, digit: u32) -> Self {
+        Self {
+            text: &line[m.range.clone()],
+            number: m.value,
+            digit,
+            start: m.range.start,
+            end: m.range.end,
+        }
+    }
+}
+
+/// How a single line was turned into its calibration value.
+#[derive(Debug, Serialize)]
+pub struct Explanation<'a> {
+    pub part: u8,
+    pub line: usize,
+    pub first: Option<Token<'a>>,
+    pub last: Option<Token<'a>>,
+    /// `None` for lines without numbers that don't count towards the sum
+    pub value: Option<usize>,
+}
+
+/// Writes explanations as they are produced, so large inputs don't have to be
+/// buffered.
+pub struct Explainer<W: Write> {
+    format: Format,
+    out: W,
+    count: usize,
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+impl<W: Write> Explainer<W> {
+    pub fn new(format: Format, mut out: W) -> io::Result<Self> {
+        match format {
+            Format::Table => writeln!(
+                out,
+                "{:>6} {:>4}  {:<16} {:>11}  {:<16} {:>11}  {:>5}",
+                "line", "part", "first", "bytes", "last", "bytes", "value"
+            )?,
+            Format::Csv => writeln!(
+                out,
+                "line,part,first,first_start,first_end,last,last_start,last_end,value"
+            )?,
+            Format::Json => write!(out, "[")?,
+        }
+        Ok(Self {
+            format,
+            out,
+            count: 0,
+        })
+    }
+
+    pub fn explain(&mut self, explanation: &Explanation) -> io::Result<()> {
+        let Explanation {
+            part,
+            line,
+            first,
+            last,
+            value,
+        } = explanation;
+        match self.format {
+            Format::Table => {
+                let column = |token: &Option<Token>| match token {
+                    Some(token) => (
+                        token.text.to_string(),
+                        format!("{}..{}", token.start, token.end),
+                    ),
+                    None => ("-".to_string(), "-".to_string()),
+                };
+                let (first, first_bytes) = column(first);
+                let (last, last_bytes) = column(last);
+                let value = value.map_or("-".to_string(), |value| value.to_string());
+                writeln!(
+                    self.out,
+                    "{line:>6} {part:>4}  {first:<16} {first_bytes:>11}  {last:<16} {last_bytes:>11}  {value:>5}",
+                )?
+            }
+            Format::Csv => {
+                let columns = |token: &Option<Token>| match token {
+                    Some(token) => {
+                        format!("{},{},{}", csv_field(token.text), token.start, token.end)
+                    }
+                    None => ",,".to_string(),
+                };
+                writeln!(
+                    self.out,
+                    "{line},{part},{},{},{}",
+                    columns(first),
+                    columns(last),
+                    value.map_or(String::new(), |value| value.to_string()),
+                )?
+            }
+            Format::Json => {
+                if self.count > 0 {
+                    write!(self.out, ",")?;
+                }
+                write!(self.out, "\n  {}", serde_json::to_string(explanation)?)?;
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.format == Format::Json {
+            writeln!(self.out, "\n]")?;
+        }
+        self.out.flush()
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::{self, Write};
+
+use clap::Args;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::vocabulary::{Language, Vocabulary};
+
+#[derive(Args, Debug)]
+pub struct GenerateArgs {
+    /// Seed for the random generator, the same seed always yields the same document
+    #[arg(long, default_value_t = 2023)]
+    seed: u64,
+    /// Number of lines to generate
+    #[arg(long, default_value_t = 1000)]
+    lines: usize,
+    /// Choose the calibration values so that the part 2 answer is exactly this
+    #[arg(long)]
+    target: Option<usize>,
+}
+
+/// Letters that don't occur in any English number word, so noise made of them
+/// can never form or extend a token.
+const SAFE_NOISE: &[u8] = b"abcdjklmpqyz";
+
+/// Number words that share letters; the solver must find both halves.
+const OVERLAPS: [&str; 8] = [
+    "twone",
+    "oneight",
+    "threeight",
+    "fiveight",
+    "sevenine",
+    "eightwo",
+    "eighthree",
+    "nineight",
+];
+
+fn noise(rng: &mut impl Rng, letters: &[u8], max: usize) -> String {
+    let len = rng.gen_range(0..=max);
+    (0..len)
+        .map(|_| *letters.choose(rng).unwrap() as char)
+        .collect()
+}
+
+/// Spells `digit` either as a digit or, if possible, as a number word.
+fn spell(rng: &mut impl Rng, words: &[String], digit: usize) -> String {
+    if digit > 0 && rng.gen_bool(0.5) {
+        words[digit - 1].clone()
+    } else {
+        digit.to_string()
+    }
+}
+
+/// Random text between the first and last token; anything goes here,
+/// including digits, number words and overlapping words.
+fn middle(rng: &mut impl Rng, words: &[String]) -> String {
+    let mut text = String::new();
+    for _ in 0..rng.gen_range(0..=4) {
+        match rng.gen_range(0..4) {
+            0 => text.push_str(&noise(rng, b"abcdefghijklmnopqrstuvwxyz", 6)),
+            1 => text.push(char::from(b'0' + rng.gen_range(0..10))),
+            2 => text.push_str(words.choose(rng).unwrap()),
+            _ => text.push_str(OVERLAPS.choose(rng).unwrap()),
+        }
+    }
+    text
+}
+
+/// Picks one calibration value (0..=99) per line so that they add up to
+/// `target`, or uniformly at random without a target.
+fn values(rng: &mut impl Rng, lines: usize, target: Option<usize>) -> io::Result<Vec<usize>> {
+    let Some(mut remaining) = target else {
+        return Ok((0..lines).map(|_| rng.gen_range(0..=99)).collect());
+    };
+    if remaining > lines * 99 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("a target of {remaining} can't be reached with {lines} lines"),
+        ));
+    }
+    Ok((0..lines)
+        .map(|idx| {
+            let rest = (lines - idx - 1) * 99;
+            let value = rng.gen_range(remaining.saturating_sub(rest)..=remaining.min(99));
+            remaining -= value;
+            value
+        })
+        .collect())
+}
+
+/// Writes a random calibration document to `out` and returns the expected
+/// answers for part 1 and part 2.
+pub fn generate(args: &GenerateArgs, out: &mut impl Write) -> io::Result<(usize, usize)> {
+    let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+    let words: Vec<String> = Vocabulary::builtin(Language::English)
+        .words()
+        .map(|(word, _)| word.to_string())
+        .collect();
+
+    let mut part1 = 0;
+    let mut part2 = 0;
+    for value in values(&mut rng, args.lines, args.target)? {
+        let first = spell(&mut rng, &words, value / 10);
+        let mut middle = middle(&mut rng, &words);
+        let last = spell(&mut rng, &words, value % 10);
+        // part 1 needs at least one digit somewhere
+        if ![&first, &middle, &last]
+            .iter()
+            .any(|text| text.bytes().any(|b| b.is_ascii_digit()))
+        {
+            middle.push(char::from(b'0' + rng.gen_range(0..10)));
+        }
+        let line = [
+            noise(&mut rng, SAFE_NOISE, 4),
+            first,
+            middle,
+            last,
+            noise(&mut rng, SAFE_NOISE, 4),
+        ]
+        .concat();
+
+        let mut digits = line.bytes().filter(u8::is_ascii_digit);
+        let first = digits.next().unwrap();
+        let last = digits.next_back().unwrap_or(first);
+        part1 += ((first - b'0') * 10 + (last - b'0')) as usize;
+        part2 += value;
+        writeln!(out, "{line}")?;
+    }
+    Ok((part1, part2))
+}
@@ -1,81 +1,406 @@
-use clap::Parser;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand, ValueEnum};
 use std::{
+    cmp::Reverse,
+    fmt::Display,
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    num::NonZeroUsize,
     path::PathBuf,
+    process::ExitCode,
+    thread,
+    time::Instant,
 };
 
+mod digits;
+mod explain;
+mod generate;
+mod parallel;
+mod scanner;
+mod vocabulary;
+
+use explain::{Explainer, Explanation, Format, Token};
+use generate::{generate, GenerateArgs};
+use scanner::{Match, Scanner};
+use vocabulary::{Language, Vocabulary};
+
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    filename: PathBuf,
-}
-
-const NUMBER_NAMES: [&str; 9] = [
-    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-];
-
-const NUMBERS: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
-
-fn main() -> std::io::Result<()> {
-    let args = Args::parse();
-    let file = File::open(args.filename)?;
-    let reader = BufReader::new(file);
-
-    let sum: usize = reader
-        .lines()
-        .map(|line| {
-            let line = line.unwrap();
-            let first_txt = NUMBER_NAMES
-                .iter()
-                .enumerate()
-                .filter_map(|(number, name)| line.find(name).map(|pos| (number + 1, pos)))
-                .min_by(|(_, apos), (_, bpos)| apos.cmp(bpos));
-            let first_num = line
-                .find(NUMBERS)
-                .map(|pos| (line.as_bytes()[pos] - b'0', pos));
-            let first = if let (Some((txt_num, txt_pos)), Some((num_num, num_pos))) =
-                (first_txt, first_num)
-            {
-                if txt_pos < num_pos {
-                    txt_num
-                } else {
-                    num_num as usize
-                }
-            } else if let Some((txt_num, _)) = first_txt {
-                txt_num
-            } else if let Some((num_num, _)) = first_num {
-                num_num as usize
-            } else {
-                panic!("Line doesn't contain any numbers!")
-            };
-            let last_txt = NUMBER_NAMES
-                .iter()
-                .enumerate()
-                .filter_map(|(number, name)| line.rfind(name).map(|pos| (number + 1, pos)))
-                .max_by(|(_, apos), (_, bpos)| apos.cmp(bpos));
-            let last_num = line
-                .rfind(NUMBERS)
-                .map(|pos| (line.as_bytes()[pos] - b'0', pos));
-            let last = if let (Some((txt_num, txt_pos)), Some((num_num, num_pos))) =
-                (last_txt, last_num)
-            {
-                if txt_pos > num_pos {
-                    txt_num
-                } else {
-                    num_num as usize
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(required = true)]
+    filename: Option<PathBuf>,
+    /// Only compute the answer for one part (both are printed otherwise)
+    #[arg(long, value_enum)]
+    part: Option<Part>,
+    /// Load number words from a file with one `word = number` mapping per line
+    #[arg(long, value_name = "FILE", conflicts_with = "language")]
+    words: Option<PathBuf>,
+    /// Built-in number words to recognise in part 2
+    #[arg(long, value_enum, default_value_t = Language::English)]
+    language: Language,
+    /// Also recognise zero, teens, tens and compounds like "twenty-three"
+    #[arg(long, conflicts_with = "words")]
+    extended: bool,
+    /// Recognise every Unicode decimal digit, not just ASCII 0-9
+    #[arg(long)]
+    unicode_digits: bool,
+    /// Match number words regardless of case
+    #[arg(short, long)]
+    ignore_case: bool,
+    /// Print how every line was decoded, as a table (default), CSV or JSON
+    #[arg(long, value_enum, value_name = "FORMAT", num_args = 0..=1, require_equals = true, default_missing_value = "table")]
+    explain: Option<Format>,
+    /// What to do with lines that don't contain any number
+    #[arg(long, value_enum, default_value_t = InvalidPolicy::Fail)]
+    on_invalid: InvalidPolicy,
+    /// Memory-map the file and process chunks of it in parallel
+    #[arg(long, conflicts_with_all = ["explain", "bench"])]
+    parallel: bool,
+    /// Number of worker threads for --parallel (defaults to the number of CPUs)
+    #[arg(long, requires = "parallel")]
+    threads: Option<NonZeroUsize>,
+    /// Time the automaton scanner against the naive per-word search
+    #[arg(long)]
+    bench: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write a random calibration document to stdout and its answers to stderr
+    Generate(GenerateArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Part {
+    /// Digits only
+    #[value(name = "1")]
+    One,
+    /// Digits and spelled-out number words
+    #[value(name = "2")]
+    Two,
+}
+
+impl Part {
+    fn number(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// Tokens recognised for this part, each mapped to the number it stands for.
+    fn tokens(self, vocabulary: &Vocabulary, unicode_digits: bool) -> Vec<(String, u32)> {
+        let digits = digits::digits(unicode_digits);
+        match self {
+            Part::One => digits.collect(),
+            Part::Two => digits
+                .chain(
+                    vocabulary
+                        .words()
+                        .map(|(word, value)| (word.to_string(), value)),
+                )
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum InvalidPolicy {
+    /// Abort on the first line without any number
+    Fail,
+    /// Leave the line out of the sum
+    Skip,
+    /// Count the line as a calibration value of zero
+    Zero,
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(io::Error),
+    NoNumbers { line: usize, content: String },
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(err) => err.fmt(f),
+            Error::NoNumbers { line, content } => {
+                write!(f, "line {line} doesn't contain any numbers: {content:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A token may stand for a number with several digits ("twenty-three",
+/// "seventeen"). As the first token of a line it contributes its leading
+/// digit, as the last token its trailing digit, so "twenty-three" reads as 2
+/// at the start of a line and as 3 at its end.
+fn leading_digit(mut number: u32) -> u32 {
+    while number >= 10 {
+        number /= 10;
+    }
+    number
+}
+
+fn trailing_digit(number: u32) -> u32 {
+    number % 10
+}
+
+fn combine(first: u32, last: u32) -> usize {
+    (leading_digit(first) * 10 + trailing_digit(last)) as usize
+}
+
+fn calibration_tokens(scanner: &Scanner, line: &str) -> Option<(Match, Match)> {
+    Some((scanner.first(line)?, scanner.last(line)?))
+}
+
+fn calibration_value(scanner: &Scanner, line: &str) -> Option<usize> {
+    let (first, last) = calibration_tokens(scanner, line)?;
+    Some(combine(first.value, last.value))
+}
+
+fn naive_calibration_value(line: &str, tokens: &[(String, u32)]) -> Option<usize> {
+    let (_, _, first) = tokens
+        .iter()
+        .filter_map(|(token, value)| {
+            line.find(token)
+                .map(|pos| (pos, Reverse(token.len()), *value))
+        })
+        .min()?;
+    let (_, _, last) = tokens
+        .iter()
+        .filter_map(|(token, value)| {
+            line.rfind(token)
+                .map(|pos| (pos + token.len(), token.len(), *value))
+        })
+        .max()?;
+    Some(combine(first, last))
+}
+
+fn bench(text: &str, parts: &[Part], vocabulary: &Vocabulary, args: &Args) {
+    for &part in parts {
+        let mut tokens = part.tokens(vocabulary, args.unicode_digits);
+        let scanner = Scanner::new(&tokens, args.ignore_case);
+        let start = Instant::now();
+        let sum: usize = text
+            .lines()
+            .filter_map(|line| calibration_value(&scanner, line))
+            .sum();
+        let automaton = start.elapsed();
+
+        let start = Instant::now();
+        let naive_sum: usize = if args.ignore_case {
+            for (token, _) in &mut tokens {
+                *token = token.to_lowercase();
+            }
+            text.lines()
+                .filter_map(|line| naive_calibration_value(&line.to_lowercase(), &tokens))
+                .sum()
+        } else {
+            text.lines()
+                .filter_map(|line| naive_calibration_value(line, &tokens))
+                .sum()
+        };
+        let naive = start.elapsed();
+
+        assert_eq!(sum, naive_sum, "scanners disagree on part {part:?}");
+        println!(
+            "Part {}: {sum} (automaton {automaton:.2?}, naive {naive:.2?}, {:.1}x)",
+            part.number(),
+            naive.as_secs_f64() / automaton.as_secs_f64()
+        );
+    }
+}
+
+/// Sum per part (or the line that made it fail) and the number of lines
+/// without numbers per part.
+type Totals = (Vec<Result<usize, Error>>, Vec<usize>);
+
+/// Reads the file line by line, optionally explaining every line. Returns the
+/// sum, or the first line without numbers under `--on-invalid fail`, and the
+/// number of lines without numbers per scanner. A part that fails doesn't stop
+/// the others.
+fn sequential(
+    file: File,
+    scanners: &[Scanner],
+    parts: &[Part],
+    args: &Args,
+) -> Result<Totals, Error> {
+    let mut explainer = args
+        .explain
+        .map(|format| Explainer::new(format, BufWriter::new(io::stdout().lock())))
+        .transpose()?;
+    let mut sums: Vec<Result<usize, Error>> = parts.iter().map(|_| Ok(0)).collect();
+    let mut invalid = vec![0usize; parts.len()];
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        if sums.iter().all(Result::is_err) {
+            break;
+        }
+        let line = line?;
+        for (part_idx, (scanner, part)) in scanners.iter().zip(parts).enumerate() {
+            if sums[part_idx].is_err() {
+                continue;
+            }
+            let tokens = calibration_tokens(scanner, &line);
+            let mut failure = None;
+            let value = match &tokens {
+                Some((first, last)) => Some(combine(first.value, last.value)),
+                None => {
+                    invalid[part_idx] += 1;
+                    match args.on_invalid {
+                        InvalidPolicy::Fail => {
+                            failure = Some(Error::NoNumbers {
+                                line: idx + 1,
+                                content: line.clone(),
+                            });
+                            None
+                        }
+                        InvalidPolicy::Skip => None,
+                        InvalidPolicy::Zero => Some(0),
+                    }
                 }
-            } else if let Some((txt_num, _)) = last_txt {
-                txt_num
-            } else if let Some((num_num, _)) = last_num {
-                num_num as usize
-            } else {
-                panic!("Line doesn't contain any numbers!")
             };
-            format!("{first}{last}").parse::<usize>().unwrap()
+            if let Some(explainer) = &mut explainer {
+                explainer.explain(&Explanation {
+                    part: part.number(),
+                    line: idx + 1,
+                    first: tokens
+                        .as_ref()
+                        .map(|(first, _)| Token::new(&line, first, leading_digit(first.value))),
+                    last: tokens
+                        .as_ref()
+                        .map(|(_, last)| Token::new(&line, last, trailing_digit(last.value))),
+                    value,
+                })?;
+            }
+            if let Some(err) = failure {
+                sums[part_idx] = Err(err);
+            } else if let Ok(sum) = &mut sums[part_idx] {
+                *sum += value.unwrap_or(0);
+            }
+        }
+    }
+    if let Some(explainer) = explainer {
+        explainer.finish()?;
+    }
+    Ok((sums, invalid))
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> Result<ExitCode, Error> {
+    if let Some(Command::Generate(generate_args)) = &args.command {
+        let mut out = BufWriter::new(io::stdout().lock());
+        let (part1, part2) = generate(generate_args, &mut out)?;
+        out.flush()?;
+        eprintln!("Part 1: {part1}");
+        eprintln!("Part 2: {part2}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let mut file = File::open(args.filename.as_ref().unwrap())?;
+    let vocabulary = match &args.words {
+        Some(path) => Vocabulary::load(path)?,
+        None if args.extended => {
+            if args.language != Language::English {
+                Args::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        "--extended is only available for English",
+                    )
+                    .exit();
+            }
+            Vocabulary::extended()
+        }
+        None => Vocabulary::builtin(args.language),
+    };
+
+    let parts = match args.part {
+        Some(part) => vec![part],
+        None => vec![Part::One, Part::Two],
+    };
+
+    if args.bench {
+        let mut text = String::new();
+        file.read_to_string(&mut text)?;
+        bench(&text, &parts, &vocabulary, &args);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let scanners: Vec<_> = parts
+        .iter()
+        .map(|part| {
+            Scanner::new(
+                &part.tokens(&vocabulary, args.unicode_digits),
+                args.ignore_case,
+            )
         })
-        .sum();
+        .collect();
+    let (sums, invalid) = if args.parallel {
+        let threads = match args.threads {
+            Some(threads) => threads,
+            None => thread::available_parallelism()?,
+        };
+        parallel::run(&file, &scanners, args.on_invalid, threads)?
+    } else {
+        sequential(file, &scanners, &parts, &args)?
+    };
 
-    println!("{sum}");
+    for (count, part) in invalid.iter().zip(&parts) {
+        let action = match args.on_invalid {
+            InvalidPolicy::Fail => continue,
+            InvalidPolicy::Skip => "skipped",
+            InvalidPolicy::Zero => "counted as zero",
+        };
+        if *count > 0 {
+            eprintln!(
+                "Part {}: {count} line(s) without numbers {action}",
+                part.number()
+            );
+        }
+    }
+
+    // with both parts, one failing still leaves the other worth printing
+    let single = sums.len() == 1;
+    let mut answers = Vec::new();
+    let mut failed = false;
+    for (sum, part) in sums.into_iter().zip(&parts) {
+        match sum {
+            Ok(sum) => answers.push((part, sum)),
+            Err(err) if single => return Err(err),
+            Err(err) => {
+                eprintln!("Part {}: {err}", part.number());
+                failed = true;
+            }
+        }
+    }
+
+    if args.explain.is_none_or(|format| format == Format::Table) {
+        for (part, sum) in answers {
+            if single {
+                println!("{sum}");
+            } else {
+                println!("Part {}: {sum}", part.number());
+            }
+        }
+    }
 
-    Ok(())
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
 }
@@ -0,0 +1,132 @@
+use std::{
+    fs::File,
+    io::{self, ErrorKind},
+    num::NonZeroUsize,
+    thread,
+};
+
+use memmap2::Mmap;
+
+use crate::{calibration_value, scanner::Scanner, Error, InvalidPolicy, Totals};
+
+/// Results of one chunk, in the same order as the scanners.
+struct Chunk {
+    lines: usize,
+    sums: Vec<usize>,
+    invalid: Vec<usize>,
+    /// First line without numbers per scanner when failing on those, as line
+    /// index within the chunk and content.
+    first_invalid: Vec<Option<(usize, String)>>,
+}
+
+/// Splits `data` into up to `count` chunks of similar size, each ending right
+/// after a newline (or at the end of the data).
+fn split(data: &[u8], count: usize) -> Vec<&[u8]> {
+    let mut chunks = Vec::with_capacity(count);
+    let mut rest = data;
+    for remaining in (1..=count).rev() {
+        if rest.is_empty() {
+            break;
+        }
+        let target = rest.len() / remaining;
+        let end = match rest[target..].iter().position(|&b| b == b'\n') {
+            Some(pos) if remaining > 1 => target + pos + 1,
+            _ => rest.len(),
+        };
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+fn process(chunk: &[u8], scanners: &[Scanner], policy: InvalidPolicy) -> io::Result<Chunk> {
+    // chunks are split at newlines, so every valid UTF-8 file yields valid chunks
+    let text =
+        std::str::from_utf8(chunk).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+    let mut result = Chunk {
+        lines: 0,
+        sums: vec![0; scanners.len()],
+        invalid: vec![0; scanners.len()],
+        first_invalid: vec![None; scanners.len()],
+    };
+    for (idx, line) in text.lines().enumerate() {
+        // line numbers after this only matter for the parts that didn't fail
+        if result.first_invalid.iter().all(Option::is_some) {
+            break;
+        }
+        result.lines += 1;
+        for (part_idx, scanner) in scanners.iter().enumerate() {
+            if result.first_invalid[part_idx].is_some() {
+                continue;
+            }
+            match calibration_value(scanner, line) {
+                Some(value) => result.sums[part_idx] += value,
+                None => {
+                    result.invalid[part_idx] += 1;
+                    if policy == InvalidPolicy::Fail {
+                        result.first_invalid[part_idx] = Some((idx, line.to_string()));
+                    }
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Memory-maps `file` and sums the calibration values of each scanner using
+/// `threads` worker threads. Returns the sums (or the first line without
+/// numbers, if failing on those) and the number of lines without numbers per
+/// scanner.
+pub fn run(
+    file: &File,
+    scanners: &[Scanner],
+    policy: InvalidPolicy,
+    threads: NonZeroUsize,
+) -> Result<Totals, Error> {
+    if file.metadata()?.len() == 0 {
+        let sums = scanners.iter().map(|_| Ok(0)).collect();
+        return Ok((sums, vec![0; scanners.len()]));
+    }
+    // SAFETY: the map is read-only and dropped before returning. Another process
+    // modifying the file meanwhile is outside our control, as with any mmap.
+    let map = unsafe { Mmap::map(file)? };
+
+    let chunks = thread::scope(|scope| {
+        let handles: Vec<_> = split(&map, threads.get())
+            .into_iter()
+            .map(|chunk| scope.spawn(move || process(chunk, scanners, policy)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("worker thread panicked"))
+            .collect::<io::Result<Vec<_>>>()
+    })?;
+
+    let mut sums: Vec<Result<usize, Error>> = scanners.iter().map(|_| Ok(0)).collect();
+    let mut invalid = vec![0; scanners.len()];
+    let mut line_offset = 0;
+    for chunk in chunks {
+        for ((total, sum), first_invalid) in
+            sums.iter_mut().zip(chunk.sums).zip(chunk.first_invalid)
+        {
+            let Ok(running) = total else {
+                continue;
+            };
+            match first_invalid {
+                Some((idx, content)) => {
+                    *total = Err(Error::NoNumbers {
+                        line: line_offset + idx + 1,
+                        content,
+                    })
+                }
+                None => *running += sum,
+            }
+        }
+        for (total, count) in invalid.iter_mut().zip(chunk.invalid) {
+            *total += count;
+        }
+        line_offset += chunk.lines;
+    }
+    Ok((sums, invalid))
+}
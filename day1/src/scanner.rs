@@ -0,0 +1,220 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub value: u32,
+    pub range: Range<usize>,
+}
+
+/// Aho-Corasick automaton compiled into a dense DFA, so scanning costs one
+/// table lookup per input byte.
+#[derive(Debug)]
+struct Automaton {
+    delta: Vec<[u32; 256]>,
+    /// Longest pattern ending in each state as `(length, value)`.
+    output: Vec<Option<(usize, u32)>>,
+    max_len: usize,
+}
+
+impl Automaton {
+    fn new<'a>(patterns: impl IntoIterator<Item = (&'a [u8], u32)>) -> Self {
+        let mut goto: Vec<[Option<u32>; 256]> = vec![[None; 256]];
+        let mut output = vec![None];
+        let mut max_len = 0;
+
+        for (pattern, value) in patterns {
+            assert!(!pattern.is_empty(), "empty patterns are not allowed");
+            let mut state = 0;
+            for &byte in pattern {
+                state = match goto[state][byte as usize] {
+                    Some(next) => next as usize,
+                    None => {
+                        goto.push([None; 256]);
+                        output.push(None);
+                        let next = goto.len() - 1;
+                        goto[state][byte as usize] = Some(next as u32);
+                        next
+                    }
+                };
+            }
+            output[state] = Some((pattern.len(), value));
+            max_len = max_len.max(pattern.len());
+        }
+
+        // breadth-first over the trie, filling in failure transitions
+        let mut delta = vec![[0u32; 256]; goto.len()];
+        let mut fail = vec![0usize; goto.len()];
+        let mut queue = std::collections::VecDeque::new();
+        for byte in 0..256 {
+            if let Some(next) = goto[0][byte] {
+                delta[0][byte] = next;
+                queue.push_back(next as usize);
+            }
+        }
+        while let Some(state) = queue.pop_front() {
+            if output[state].is_none() {
+                output[state] = output[fail[state]];
+            }
+            for byte in 0..256 {
+                match goto[state][byte] {
+                    Some(next) => {
+                        fail[next as usize] = delta[fail[state]][byte] as usize;
+                        delta[state][byte] = next;
+                        queue.push_back(next as usize);
+                    }
+                    None => delta[state][byte] = delta[fail[state]][byte],
+                }
+            }
+        }
+
+        Self {
+            delta,
+            output,
+            max_len,
+        }
+    }
+
+    /// Finds the leftmost match, preferring the longest pattern on ties.
+    fn leftmost(&self, haystack: impl Iterator<Item = u8>) -> Option<(u32, Range<usize>)> {
+        let mut state = 0;
+        let mut best: Option<(u32, Range<usize>)> = None;
+        for (idx, byte) in haystack.enumerate() {
+            if let Some((_, range)) = &best {
+                if idx >= range.start + self.max_len {
+                    break;
+                }
+            }
+            state = self.delta[state][byte as usize] as usize;
+            if let Some((len, value)) = self.output[state] {
+                let range = (idx + 1 - len)..(idx + 1);
+                let better = match &best {
+                    Some((_, best)) => {
+                        range.start < best.start
+                            || (range.start == best.start && range.end > best.end)
+                    }
+                    None => true,
+                };
+                if better {
+                    best = Some((value, range));
+                }
+            }
+        }
+        best
+    }
+}
+
+/// Finds the first and last number token in a line, with one forward and one
+/// backward pass. Overlapping tokens ("twone", "eightwo") are handled because
+/// both directions are scanned independently.
+#[derive(Debug)]
+pub struct Scanner {
+    forward: Automaton,
+    backward: Automaton,
+    ignore_case: bool,
+}
+
+/// Lower-cased copy of a line that remembers, for every byte, the byte range
+/// of the original character it came from. Lower-casing may change the UTF-8
+/// length of a character, so matches have to be mapped back through this.
+struct Folded {
+    text: String,
+    origin: Vec<Range<usize>>,
+}
+
+impl Folded {
+    fn new(line: &str) -> Self {
+        let mut text = String::with_capacity(line.len());
+        let mut origin = Vec::with_capacity(line.len());
+        for (pos, c) in line.char_indices() {
+            let source = pos..(pos + c.len_utf8());
+            for lower in c.to_lowercase() {
+                text.push(lower);
+                origin.resize(text.len(), source.clone());
+            }
+        }
+        Self { text, origin }
+    }
+
+    fn original(&self, m: Match) -> Match {
+        Match {
+            value: m.value,
+            range: self.origin[m.range.start].start..self.origin[m.range.end - 1].end,
+        }
+    }
+}
+
+impl Scanner {
+    pub fn new(tokens: &[(String, u32)], ignore_case: bool) -> Self {
+        let tokens: Vec<(String, u32)> = tokens
+            .iter()
+            .map(|(token, value)| {
+                let token = if ignore_case {
+                    token.to_lowercase()
+                } else {
+                    token.clone()
+                };
+                (token, *value)
+            })
+            .collect();
+        let reversed: Vec<(Vec<u8>, u32)> = tokens
+            .iter()
+            .map(|(token, value)| (token.bytes().rev().collect(), *value))
+            .collect();
+        Self {
+            forward: Automaton::new(
+                tokens
+                    .iter()
+                    .map(|(token, value)| (token.as_bytes(), *value)),
+            ),
+            backward: Automaton::new(
+                reversed
+                    .iter()
+                    .map(|(token, value)| (token.as_slice(), *value)),
+            ),
+            ignore_case,
+        }
+    }
+
+    pub fn first(&self, line: &str) -> Option<Match> {
+        if !self.ignore_case {
+            return self.first_in(line.bytes());
+        }
+        if line.is_ascii() {
+            return self.first_in(line.bytes().map(|b| b.to_ascii_lowercase()));
+        }
+        let folded = Folded::new(line);
+        self.first_in(folded.text.bytes())
+            .map(|m| folded.original(m))
+    }
+
+    pub fn last(&self, line: &str) -> Option<Match> {
+        if !self.ignore_case {
+            return self.last_in(line.bytes());
+        }
+        if line.is_ascii() {
+            return self.last_in(line.bytes().map(|b| b.to_ascii_lowercase()));
+        }
+        let folded = Folded::new(line);
+        self.last_in(folded.text.bytes())
+            .map(|m| folded.original(m))
+    }
+
+    fn first_in(&self, bytes: impl Iterator<Item = u8>) -> Option<Match> {
+        self.forward
+            .leftmost(bytes)
+            .map(|(value, range)| Match { value, range })
+    }
+
+    fn last_in(
+        &self,
+        bytes: impl DoubleEndedIterator<Item = u8> + ExactSizeIterator,
+    ) -> Option<Match> {
+        let len = bytes.len();
+        self.backward
+            .leftmost(bytes.rev())
+            .map(|(value, range)| Match {
+                value,
+                range: (len - range.end)..(len - range.start),
+            })
+    }
+}
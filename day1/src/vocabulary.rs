@@ -0,0 +1,134 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use clap::ValueEnum;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Language {
+    #[value(alias = "en")]
+    English,
+    #[value(alias = "de")]
+    German,
+    #[value(alias = "fr")]
+    French,
+    #[value(alias = "es")]
+    Spanish,
+    #[value(alias = "nl")]
+    Dutch,
+}
+
+impl Language {
+    fn names(self) -> [&'static str; 9] {
+        match self {
+            Language::English => [
+                "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+            ],
+            Language::German => [
+                "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+            ],
+            Language::French => [
+                "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf",
+            ],
+            Language::Spanish => [
+                "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve",
+            ],
+            Language::Dutch => [
+                "een", "twee", "drie", "vier", "vijf", "zes", "zeven", "acht", "negen",
+            ],
+        }
+    }
+}
+
+const ENGLISH_TEENS: [&str; 10] = [
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const ENGLISH_TENS: [&str; 8] = [
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Mapping of spelled-out number words to the number they stand for.
+#[derive(Debug, Clone)]
+pub struct Vocabulary(Vec<(String, u32)>);
+
+impl Vocabulary {
+    pub fn builtin(language: Language) -> Self {
+        Self(
+            language
+                .names()
+                .iter()
+                .enumerate()
+                .map(|(number, name)| (name.to_string(), number as u32 + 1))
+                .collect(),
+        )
+    }
+
+    /// English words for every number from zero to ninety-nine, with compounds
+    /// written hyphenated ("twenty-three").
+    pub fn extended() -> Self {
+        let mut words = vec![("zero".to_string(), 0)];
+        words.extend(
+            Language::English
+                .names()
+                .iter()
+                .chain(&ENGLISH_TEENS)
+                .enumerate()
+                .map(|(number, name)| (name.to_string(), number as u32 + 1)),
+        );
+        for (tens, name) in ENGLISH_TENS.iter().enumerate() {
+            let tens = (tens as u32 + 2) * 10;
+            words.push((name.to_string(), tens));
+            for (ones, unit) in Language::English.names().iter().enumerate() {
+                words.push((format!("{name}-{unit}"), tens + ones as u32 + 1));
+            }
+        }
+        Self(words)
+    }
+
+    /// Loads a vocabulary with one `word = number` mapping per line. Words may
+    /// contain spaces, blank lines and lines starting with `#` are ignored.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let mut words = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |msg: &str| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{}:{}: {msg}", path.display(), idx + 1),
+                )
+            };
+            let Some((word, value)) = line.rsplit_once('=') else {
+                return Err(invalid("expected `word = number`"));
+            };
+            let word = word.trim();
+            if word.is_empty() {
+                return Err(invalid("empty word"));
+            }
+            let Ok(value) = value.trim().parse::<u32>() else {
+                return Err(invalid("value must be a non-negative number"));
+            };
+            words.push((word.to_string(), value));
+        }
+        Ok(Self(words))
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.0.iter().map(|(word, value)| (word.as_str(), *value))
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Number of cubes per colour.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bag(pub BTreeMap<String, usize>);
+
+impl Bag {
+    pub fn count(&self, colour: &str) -> usize {
+        self.0.get(colour).copied().unwrap_or(0)
+    }
+}
+
+impl FromStr for Bag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bag = Bag::default();
+        for entry in s.split(',') {
+            let Some((name, count)) = entry.trim().split_once('=') else {
+                return Err(format!("expected `colour=count`, got `{entry}`"));
+            };
+            // the same colour names the game parser accepts
+            if name.is_empty() {
+                return Err(format!("missing colour in `{entry}`"));
+            }
+            if !name.chars().all(char::is_alphabetic) {
+                return Err(format!("invalid colour `{name}`, expected letters only"));
+            }
+            let count = count
+                .parse::<usize>()
+                .map_err(|err| format!("invalid count for {name}: {err}"))?;
+            bag.0.insert(name.to_string(), count);
+        }
+        Ok(bag)
+    }
+}
+
+/// One handful of cubes shown from the bag, in the order they were listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draw {
+    pub cubes: Vec<(String, usize)>,
+}
+
+impl Display for Draw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (colour, count)) in self.cubes.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{count} {colour}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    pub id: usize,
+    pub draws: Vec<Draw>,
+}
+
+impl Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Game {}: ", self.id)?;
+        for (idx, draw) in self.draws.iter().enumerate() {
+            if idx > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{draw}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Game {
+    /// Parses a line like `Game 3: 8 green, 6 blue; 5 blue, 4 red`.
+    /// `line` is the 1-based line number used in error messages.
+    pub fn parse(line: usize, text: &str) -> Result<Self, ParseError> {
+        let mut cursor = Cursor { line, text, pos: 0 };
+        cursor.expect("Game")?;
+        if !cursor.skip_whitespace() {
+            return Err(cursor.error("expected whitespace"));
+        }
+        let id = cursor.number("game id")?;
+        cursor.skip_whitespace();
+        cursor.expect(":")?;
+
+        let mut draws = Vec::new();
+        loop {
+            draws.push(cursor.draw()?);
+            cursor.skip_whitespace();
+            if !cursor.eat(";") {
+                break;
+            }
+        }
+        if !cursor.at_end() {
+            return Err(cursor.error("expected `,`, `;` or end of line"));
+        }
+        Ok(Self { id, draws })
+    }
+
+    /// The fewest cubes of each colour that make every draw possible.
+    pub fn minimal_bag(&self) -> Bag {
+        let mut bag = Bag::default();
+        for draw in &self.draws {
+            for (colour, count) in &draw.cubes {
+                let max = bag.0.entry(colour.clone()).or_default();
+                *max = (*max).max(*count);
+            }
+        }
+        bag
+    }
+}
+
+/// Malformed input at a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Cursor<'a> {
+    line: usize,
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.text[..self.pos].chars().count() + 1,
+            message: message.to_string(),
+        }
+    }
+
+    /// Returns whether any whitespace was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = self.rest();
+        let trimmed = rest.trim_start();
+        self.pos += rest.len() - trimmed.len();
+        rest.len() != trimmed.len()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{token}`")))
+        }
+    }
+
+    fn take_while(
+        &mut self,
+        what: &str,
+        pred: impl Fn(char) -> bool,
+    ) -> Result<&'a str, ParseError> {
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(&format!("expected {what}")));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn number(&mut self, what: &str) -> Result<usize, ParseError> {
+        let start = self.pos;
+        let digits = self.take_while(what, |c| c.is_ascii_digit())?;
+        digits.parse().map_err(|_| {
+            self.pos = start;
+            self.error(&format!("{what} out of range"))
+        })
+    }
+
+    fn draw(&mut self) -> Result<Draw, ParseError> {
+        let mut cubes: Vec<(String, usize)> = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let count = self.number("count")?;
+            if !self.skip_whitespace() {
+                return Err(self.error("expected whitespace"));
+            }
+            let start = self.pos;
+            let colour = self.take_while("colour", char::is_alphabetic)?;
+            if cubes.iter().any(|(seen, _)| seen == colour) {
+                self.pos = start;
+                return Err(self.error(&format!("duplicate colour `{colour}` in draw")));
+            }
+            cubes.push((colour.to_string(), count));
+            self.skip_whitespace();
+            if !self.eat(",") {
+                return Ok(Draw { cubes });
+            }
+        }
+    }
+}
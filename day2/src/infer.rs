@@ -0,0 +1,85 @@
+use crate::game::{Bag, Game};
+
+/// Smallest number of cubes per colour that makes every game possible,
+/// together with the ID of the (first) game that needs that many.
+/// `minimal_bags` are the minimal bags of `games`, in the same order.
+pub fn minimum_bag(
+    games: &[Game],
+    minimal_bags: &[Bag],
+    colours: &[String],
+) -> Vec<(String, usize, Option<usize>)> {
+    colours
+        .iter()
+        .map(|colour| {
+            let mut needed = 0;
+            let mut game_id = None;
+            for (game, bag) in games.iter().zip(minimal_bags) {
+                let count = bag.count(colour);
+                if count > needed {
+                    needed = count;
+                    game_id = Some(game.id);
+                }
+            }
+            (colour.clone(), needed, game_id)
+        })
+        .collect()
+}
+
+/// Number of ways to distribute `spare` extra cubes over `colours` colours,
+/// or `None` if that doesn't fit in a `u128`.
+pub fn composition_count(spare: usize, colours: usize) -> Option<u128> {
+    if colours == 0 {
+        return Some(u128::from(spare == 0));
+    }
+    // stars and bars: (spare + colours - 1) choose (colours - 1)
+    let n = (spare + colours - 1) as u128;
+    let k = (colours - 1) as u128;
+    let mut result: u128 = 1;
+    for i in 0..k {
+        result = result.checked_mul(n - i)? / (i + 1);
+    }
+    Some(result)
+}
+
+/// Every bag with exactly `total` cubes that holds at least `minimum` of each
+/// colour, in lexicographic order of the counts. Stops after `limit` bags.
+pub fn compositions(minimum: &[(String, usize)], total: usize, limit: usize) -> Vec<Bag> {
+    fn fill(
+        minimum: &[(String, usize)],
+        spare: usize,
+        current: &mut Vec<usize>,
+        limit: usize,
+        out: &mut Vec<Bag>,
+    ) {
+        if out.len() >= limit {
+            return;
+        }
+        let idx = current.len();
+        if idx + 1 == minimum.len() {
+            current.push(minimum[idx].1 + spare);
+            out.push(Bag(minimum
+                .iter()
+                .map(|(colour, _)| colour.clone())
+                .zip(current.iter().copied())
+                .collect()));
+            current.pop();
+            return;
+        }
+        for extra in 0..=spare {
+            current.push(minimum[idx].1 + extra);
+            fill(minimum, spare - extra, current, limit, out);
+            current.pop();
+            // stop right away, `spare` can be far too large to run through
+            if out.len() >= limit {
+                return;
+            }
+        }
+    }
+
+    let needed: usize = minimum.iter().map(|(_, count)| count).sum();
+    let mut out = Vec::new();
+    if total >= needed && !minimum.is_empty() {
+        fill(minimum, total - needed, &mut Vec::new(), limit, &mut out);
+    }
+    out
+}
@@ -1,64 +1,330 @@
 use std::{
+    collections::BTreeSet,
+    fmt::{self, Display},
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, BufWriter, Write},
     path::PathBuf,
+    process::ExitCode,
 };
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+
+mod game;
+mod infer;
+mod query;
+mod simulate;
+
+use game::{Bag, Game, ParseError};
+use query::Expr;
+use simulate::{simulate, SimulateArgs};
 
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    filename: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(required = true)]
+    filename: Option<PathBuf>,
+    /// Only compute the answer for one part (both are printed otherwise)
+    #[arg(long, value_enum)]
+    part: Option<Part>,
+    /// Bag contents that part 1 checks every draw against
+    #[arg(long, default_value = "red=12,green=13,blue=14")]
+    bag: Bag,
+    /// Colours the power is computed over (defaults to every colour in the input)
+    #[arg(long, value_delimiter = ',')]
+    colours: Option<Vec<String>>,
+    /// Report the smallest bag consistent with every game instead of the answers
+    #[arg(long)]
+    infer: bool,
+    /// With --infer, list the bags with exactly this many cubes that are consistent with every game
+    #[arg(long, requires = "infer")]
+    total: Option<usize>,
+    /// Maximum number of bags listed for --total
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+    /// Only list the games matching this expression, e.g. "any(red > 10 and blue < 3)"
+    /// or "draws > 4"; names are colours, `total`, `id`, `draws` or `power`
+    #[arg(long = "where", value_name = "EXPR")]
+    filter: Option<Expr>,
+    /// Aggregates to print for the games matching --where
+    #[arg(long, value_enum, value_delimiter = ',', requires = "filter")]
+    aggregate: Vec<Aggregate>,
+    /// What to do with colours that are not in --colours
+    #[arg(long, value_enum, default_value_t = UnknownColour::Warn)]
+    unknown_colour: UnknownColour,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write random games to stdout and their answers to stderr
+    Simulate(SimulateArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Part {
+    /// Sum of the IDs of games that are possible with the bag
+    #[value(name = "1")]
+    One,
+    /// Sum of the powers of the minimal bag of each game
+    #[value(name = "2")]
+    Two,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Aggregate {
+    /// Number of matching games
+    Count,
+    /// Sum of the IDs of matching games
+    Ids,
+    /// Sum of the powers of matching games
+    Powers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum UnknownColour {
+    /// Print a warning and leave the colour out of the power
+    Warn,
+    /// Abort with an error
+    Error,
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(io::Error),
+    Parse(ParseError),
+    UnknownColour { game: usize, colour: String },
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => err.fmt(f),
+            Error::Parse(err) => err.fmt(f),
+            Error::UnknownColour { game, colour } => {
+                write!(f, "game {game}: colour `{colour}` is not in --colours")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Reads one game per line, skipping blank lines.
+fn read_games(reader: impl BufRead) -> Result<Vec<Game>, Error> {
+    let mut games = Vec::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        games.push(Game::parse(idx + 1, &line)?);
+    }
+    Ok(games)
+}
+
+/// The first draw of a game that needs more cubes of a colour than the bag has.
+#[derive(Debug)]
+struct Violation {
+    draw: usize,
+    text: String,
+    colour: String,
+    count: usize,
+    available: usize,
+}
+
+/// Finds the first draw that needs more cubes of a colour than `bag` holds.
+fn feasibility(game: &Game, bag: &Bag) -> Option<Violation> {
+    game.draws.iter().enumerate().find_map(|(idx, draw)| {
+        draw.cubes.iter().find_map(|(colour, count)| {
+            let available = bag.count(colour);
+            (*count > available).then(|| Violation {
+                draw: idx + 1,
+                text: draw.to_string(),
+                colour: colour.clone(),
+                count: *count,
+                available,
+            })
+        })
+    })
+}
+
+fn report_inference(games: &[Game], minimal_bags: &[Bag], colours: &[String], args: &Args) {
+    let minimum = infer::minimum_bag(games, minimal_bags, colours);
+    let needed: usize = minimum.iter().map(|(_, count, _)| count).sum();
+    let described: Vec<String> = minimum
+        .iter()
+        .map(|(colour, count, game)| match game {
+            Some(game) => format!("{count} {colour} (game {game})"),
+            None => format!("0 {colour}"),
+        })
+        .collect();
+    println!("Minimum bag: {} = {needed} cubes", described.join(", "));
+
+    let Some(total) = args.total else {
+        return;
+    };
+    let minimum: Vec<(String, usize)> = minimum
+        .into_iter()
+        .map(|(colour, count, _)| (colour, count))
+        .collect();
+    let count = if total < needed {
+        Some(0)
+    } else {
+        infer::composition_count(total - needed, minimum.len())
+    };
+    match count {
+        Some(count) => println!("Bags with {total} cubes consistent with every game: {count}"),
+        None => println!("Bags with {total} cubes consistent with every game: too many to count"),
+    }
+    for bag in infer::compositions(&minimum, total, args.limit) {
+        let described: Vec<String> = colours
+            .iter()
+            .map(|colour| format!("{} {colour}", bag.count(colour)))
+            .collect();
+        println!("  {}", described.join(", "));
+    }
+    if count.is_none_or(|count| count > args.limit as u128) {
+        println!("  ...");
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
 
-fn main() -> std::io::Result<()> {
-    let args = Args::parse();
-
-    let file = File::open(args.filename)?;
-    let reader = BufReader::new(file);
-
-    let sum: usize = reader
-        .lines()
-        .filter_map(|line| {
-            let line = line.unwrap();
-            let Some((_, def)) = line.split_once(": ") else {
-                return None;
-            };
-
-            let mut max_red = 0;
-            let mut max_green = 0;
-            let mut max_blue = 0;
-
-            for draw in def.split("; ") {
-                for pick in draw.split(", ") {
-                    if let Some((count, name)) = pick.split_once(' ') {
-                        let count = count.parse::<usize>().unwrap();
-                        match name {
-                            "red" => {
-                                if max_red < count {
-                                    max_red = count;
-                                }
-                            }
-                            "green" => {
-                                if max_green < count {
-                                    max_green = count;
-                                }
-                            }
-                            "blue" => {
-                                if max_blue < count {
-                                    max_blue = count;
-                                }
-                            }
-                            _ => return None,
-                        }
+fn run(args: Args) -> Result<(), Error> {
+    if let Some(Command::Simulate(simulate_args)) = &args.command {
+        let mut out = BufWriter::new(io::stdout().lock());
+        let (id_sum, power_sum) = simulate(simulate_args, &mut out)?;
+        out.flush()?;
+        eprintln!("Part 1: {id_sum}");
+        eprintln!("Part 2: {power_sum}");
+        return Ok(());
+    }
+
+    let file = File::open(args.filename.as_ref().unwrap())?;
+    let games = read_games(BufReader::new(file))?;
+
+    let minimal_bags: Vec<Bag> = games.iter().map(Game::minimal_bag).collect();
+    let declared: Option<BTreeSet<&str>> = args
+        .colours
+        .as_ref()
+        .map(|colours| colours.iter().map(String::as_str).collect());
+    if let Some(declared) = &declared {
+        for (game, bag) in games.iter().zip(&minimal_bags) {
+            for colour in bag.0.keys() {
+                if !declared.contains(colour.as_str()) {
+                    let err = Error::UnknownColour {
+                        game: game.id,
+                        colour: colour.clone(),
+                    };
+                    match args.unknown_colour {
+                        UnknownColour::Warn => eprintln!("warning: {err}"),
+                        UnknownColour::Error => return Err(err),
                     }
                 }
             }
+        }
+    }
 
-            Some(max_red * max_green * max_blue)
-        })
-        .sum();
+    // a colour that never shows up in a game needs zero cubes, so the power of
+    // that game is zero, just like with the fixed red/green/blue set
+    let power_colours: Vec<String> = match &declared {
+        Some(declared) => declared.iter().map(|colour| colour.to_string()).collect(),
+        None => minimal_bags
+            .iter()
+            .flat_map(|bag| bag.0.keys().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+    };
+    if args.infer {
+        report_inference(&games, &minimal_bags, &power_colours, &args);
+        return Ok(());
+    }
+    let power = |bag: &Bag| {
+        power_colours
+            .iter()
+            .map(|colour| bag.count(colour))
+            .product::<usize>()
+    };
+    if let Some(filter) = &args.filter {
+        let matching: Vec<(&Game, &Bag)> = games
+            .iter()
+            .zip(&minimal_bags)
+            .filter(|(game, _)| filter.matches(game, &power_colours))
+            .collect();
+        let ids: Vec<String> = matching
+            .iter()
+            .map(|(game, _)| game.id.to_string())
+            .collect();
+        println!("Matching games: {}", ids.join(", "));
+        for aggregate in &args.aggregate {
+            match aggregate {
+                Aggregate::Count => println!("Count: {}", matching.len()),
+                Aggregate::Ids => println!(
+                    "Sum of IDs: {}",
+                    matching.iter().map(|(game, _)| game.id).sum::<usize>()
+                ),
+                Aggregate::Powers => println!(
+                    "Sum of powers: {}",
+                    matching.iter().map(|(_, bag)| power(bag)).sum::<usize>()
+                ),
+            }
+        }
+        return Ok(());
+    }
+
+    let mut id_sum = 0;
+    let mut infeasible = Vec::new();
+    for game in &games {
+        match feasibility(game, &args.bag) {
+            Some(violation) => infeasible.push((game.id, violation)),
+            None => id_sum += game.id,
+        }
+    }
+
+    let power_sum: usize = minimal_bags.iter().map(power).sum();
+
+    if args.part != Some(Part::Two) {
+        for (id, violation) in &infeasible {
+            println!(
+                "Game {id} is impossible: draw {} ({}) has {} {}, but the bag only holds {}",
+                violation.draw,
+                violation.text,
+                violation.count,
+                violation.colour,
+                violation.available
+            );
+        }
+    }
 
-    println!("{sum}");
+    match args.part {
+        Some(Part::One) => println!("{id_sum}"),
+        Some(Part::Two) => println!("{power_sum}"),
+        None => {
+            println!("Part 1: {id_sum}");
+            println!("Part 2: {power_sum}");
+        }
+    }
     Ok(())
 }
@@ -0,0 +1,365 @@
+//! A small predicate language over games, e.g.
+//! `any(red > 10 and blue < 3)` or `draws > 4 or not power >= 100`.
+//!
+//! Outside of `any(...)`/`all(...)` a colour name stands for the most cubes of
+//! that colour shown in any draw of the game; inside, it's the count in the
+//! draw at hand. `total` is the number of cubes in that bag or draw, `id`,
+//! `draws` and `power` always refer to the game. Any other name is a colour.
+
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::game::{Draw, Game};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Op {
+    fn apply(self, left: usize, right: usize) -> bool {
+        match self {
+            Op::Less => left < right,
+            Op::LessEqual => left <= right,
+            Op::Greater => left > right,
+            Op::GreaterEqual => left >= right,
+            Op::Equal => left == right,
+            Op::NotEqual => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Number(usize),
+    Name(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Value, Op, Value),
+    Any(Box<Expr>),
+    All(Box<Expr>),
+}
+
+/// What names are resolved against while evaluating.
+struct Scope<'a> {
+    game: &'a Game,
+    draw: Option<&'a Draw>,
+    colours: &'a [String],
+}
+
+impl Scope<'_> {
+    fn resolve(&self, value: &Value) -> usize {
+        let name = match value {
+            Value::Number(number) => return *number,
+            Value::Name(name) => name.as_str(),
+        };
+        match (name, self.draw) {
+            ("id", _) => self.game.id,
+            ("draws", _) => self.game.draws.len(),
+            ("power", _) => {
+                let bag = self.game.minimal_bag();
+                self.colours
+                    .iter()
+                    .map(|colour| bag.count(colour))
+                    .product()
+            }
+            ("total", Some(draw)) => draw.cubes.iter().map(|(_, count)| count).sum(),
+            ("total", None) => self.game.minimal_bag().0.values().sum(),
+            (colour, Some(draw)) => draw
+                .cubes
+                .iter()
+                .find(|(name, _)| name == colour)
+                .map_or(0, |(_, count)| *count),
+            (colour, None) => self.game.minimal_bag().count(colour),
+        }
+    }
+
+    fn eval(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Or(left, right) => self.eval(left) || self.eval(right),
+            Expr::And(left, right) => self.eval(left) && self.eval(right),
+            Expr::Not(inner) => !self.eval(inner),
+            Expr::Compare(left, op, right) => op.apply(self.resolve(left), self.resolve(right)),
+            Expr::Any(inner) => self
+                .game
+                .draws
+                .iter()
+                .any(|draw| self.in_draw(draw).eval(inner)),
+            Expr::All(inner) => self
+                .game
+                .draws
+                .iter()
+                .all(|draw| self.in_draw(draw).eval(inner)),
+        }
+    }
+
+    fn in_draw<'a>(&'a self, draw: &'a Draw) -> Scope<'a> {
+        Scope {
+            game: self.game,
+            draw: Some(draw),
+            colours: self.colours,
+        }
+    }
+}
+
+impl Expr {
+    /// Whether `game` satisfies the expression. `colours` are the colours the
+    /// power is computed over.
+    pub fn matches(&self, game: &Game, colours: &[String]) -> bool {
+        Scope {
+            game,
+            draw: None,
+            colours,
+        }
+        .eval(self)
+    }
+}
+
+/// Malformed query at a 1-based column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.column)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(usize),
+    Name(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    End,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "`{number}`"),
+            Token::Name(name) => write!(f, "`{name}`"),
+            Token::Op(_) => write!(f, "comparison"),
+            Token::And => write!(f, "`and`"),
+            Token::Or => write!(f, "`or`"),
+            Token::Not => write!(f, "`not`"),
+            Token::Open => write!(f, "`(`"),
+            Token::Close => write!(f, "`)`"),
+            Token::End => write!(f, "end of query"),
+        }
+    }
+}
+
+/// Splits the query into tokens, each with its 1-based column.
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        let column = idx + 1;
+        if c.is_whitespace() {
+            idx += 1;
+            continue;
+        }
+        let two: String = chars[idx..(idx + 2).min(chars.len())].iter().collect();
+        let (token, len) = match (c, two.as_str()) {
+            (_, "<=") => (Token::Op(Op::LessEqual), 2),
+            (_, ">=") => (Token::Op(Op::GreaterEqual), 2),
+            (_, "==") => (Token::Op(Op::Equal), 2),
+            (_, "!=") => (Token::Op(Op::NotEqual), 2),
+            (_, "&&") => (Token::And, 2),
+            (_, "||") => (Token::Or, 2),
+            ('<', _) => (Token::Op(Op::Less), 1),
+            ('>', _) => (Token::Op(Op::Greater), 1),
+            ('=', _) => (Token::Op(Op::Equal), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            _ if c.is_ascii_digit() => {
+                let len = chars[idx..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .count();
+                let digits: String = chars[idx..idx + len].iter().collect();
+                let number = digits.parse().map_err(|_| QueryError {
+                    column,
+                    message: "number out of range".to_string(),
+                })?;
+                (Token::Number(number), len)
+            }
+            _ if c.is_alphabetic() => {
+                let len = chars[idx..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .count();
+                let word: String = chars[idx..idx + len].iter().collect();
+                let token = match word.as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Name(word),
+                };
+                (token, len)
+            }
+            _ => {
+                return Err(QueryError {
+                    column,
+                    message: format!("unexpected character `{c}`"),
+                })
+            }
+        };
+        tokens.push((column, token));
+        idx += len;
+    }
+    tokens.push((chars.len() + 1, Token::End));
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    in_draw: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].1.clone();
+        if token != Token::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, expected: &str) -> QueryError {
+        let (column, found) = &self.tokens[self.pos];
+        QueryError {
+            column: *column,
+            message: format!("expected {expected}, found {found}"),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.and()?;
+        while *self.peek() == Token::Or {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut expr = self.not()?;
+        while *self.peek() == Token::And {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        if *self.peek() == Token::Not {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, QueryError> {
+        if *self.peek() == Token::Open {
+            self.next();
+            let expr = self.or()?;
+            return self.close(expr);
+        }
+        if let Token::Name(name) = self.peek() {
+            let quantifier = name == "any" || name == "all";
+            if quantifier && self.tokens[self.pos + 1].1 == Token::Open {
+                if self.in_draw {
+                    return Err(self.error("a comparison (`any`/`all` can't be nested)"));
+                }
+                let all = name == "all";
+                self.next();
+                self.next();
+                self.in_draw = true;
+                let inner = Box::new(self.or()?);
+                self.in_draw = false;
+                return self.close(if all {
+                    Expr::All(inner)
+                } else {
+                    Expr::Any(inner)
+                });
+            }
+        }
+        let left = self.value()?;
+        let Token::Op(op) = *self.peek() else {
+            return Err(self.error("comparison"));
+        };
+        self.next();
+        let right = self.value()?;
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn value(&mut self) -> Result<Value, QueryError> {
+        match self.peek().clone() {
+            Token::Number(number) => {
+                self.next();
+                Ok(Value::Number(number))
+            }
+            Token::Name(name) => {
+                self.next();
+                Ok(Value::Name(name))
+            }
+            _ => Err(self.error("number or name")),
+        }
+    }
+
+    fn close(&mut self, expr: Expr) -> Result<Expr, QueryError> {
+        if *self.peek() != Token::Close {
+            return Err(self.error("`)`"));
+        }
+        self.next();
+        Ok(expr)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            in_draw: false,
+        };
+        let expr = parser.or()?;
+        if *parser.peek() != Token::End {
+            return Err(parser.error("`and`, `or` or end of query"));
+        }
+        Ok(expr)
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+use clap::Args;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::game::{Bag, Draw, Game};
+
+#[derive(Args, Debug)]
+pub struct SimulateArgs {
+    /// Bag the cubes are drawn from
+    #[arg(long, default_value = "red=12,green=13,blue=14")]
+    bag: Bag,
+    /// Number of games to generate
+    #[arg(long, default_value_t = 100)]
+    games: usize,
+    /// Fewest draws per game
+    #[arg(long, default_value_t = 1)]
+    min_draws: usize,
+    /// Most draws per game
+    #[arg(long, default_value_t = 6)]
+    max_draws: usize,
+    /// Fraction of games drawn from a bag with twice as many cubes, which are
+    /// then likely impossible with --bag
+    #[arg(long, default_value_t = 0.0)]
+    impossible: f64,
+    /// Seed for the random generator, the same seed always yields the same games
+    #[arg(long, default_value_t = 2023)]
+    seed: u64,
+}
+
+/// Takes a random handful of cubes from the bag. The cubes are put back
+/// afterwards, so every draw starts from the full bag.
+fn draw(rng: &mut impl Rng, cubes: &[&str]) -> Draw {
+    let size = rng.gen_range(1..=cubes.len());
+    let mut handful: Vec<(String, usize)> = Vec::new();
+    for colour in cubes.choose_multiple(rng, size) {
+        match handful.iter_mut().find(|(seen, _)| seen == colour) {
+            Some((_, count)) => *count += 1,
+            None => handful.push((colour.to_string(), 1)),
+        }
+    }
+    Draw { cubes: handful }
+}
+
+fn fits(game: &Game, bag: &Bag) -> bool {
+    game.draws.iter().all(|draw| {
+        draw.cubes
+            .iter()
+            .all(|(colour, count)| *count <= bag.count(colour))
+    })
+}
+
+/// Writes random games to `out` and returns the expected answers for part 1
+/// (checked against the simulation bag) and part 2.
+pub fn simulate(args: &SimulateArgs, out: &mut impl Write) -> io::Result<(usize, usize)> {
+    if args.min_draws == 0 || args.min_draws > args.max_draws {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "draws per game must satisfy 1 <= --min-draws <= --max-draws",
+        ));
+    }
+    if args.bag.0.values().sum::<usize>() == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the bag needs at least one cube",
+        ));
+    }
+    let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+    let cubes = |factor: usize| -> Vec<&str> {
+        args.bag
+            .0
+            .iter()
+            .flat_map(|(colour, count)| std::iter::repeat_n(colour.as_str(), count * factor))
+            .collect()
+    };
+    let (regular, doubled) = (cubes(1), cubes(2));
+
+    let mut id_sum = 0;
+    let mut minimal_bags = Vec::new();
+    for id in 1..=args.games {
+        let cubes = if rng.gen_bool(args.impossible.clamp(0.0, 1.0)) {
+            &doubled
+        } else {
+            &regular
+        };
+        let draws = (0..rng.gen_range(args.min_draws..=args.max_draws))
+            .map(|_| draw(&mut rng, cubes))
+            .collect();
+        let game = Game { id, draws };
+        if fits(&game, &args.bag) {
+            id_sum += id;
+        }
+        minimal_bags.push(game.minimal_bag());
+        writeln!(out, "{game}")?;
+    }
+
+    // same rule as the solver: the power covers every colour in the input
+    let colours: BTreeSet<&String> = minimal_bags.iter().flat_map(|bag| bag.0.keys()).collect();
+    let power_sum = minimal_bags
+        .iter()
+        .map(|bag| {
+            colours
+                .iter()
+                .map(|colour| bag.count(colour))
+                .product::<usize>()
+        })
+        .sum();
+    Ok((id_sum, power_sum))
+}
@@ -0,0 +1,106 @@
+use std::time::Instant;
+
+use clap::Args;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{grid::Adjacency, index::PartIndex, parse, Error, Part};
+
+#[derive(Args, Debug)]
+pub struct BenchArgs {
+    /// Width and height of the generated schematic
+    #[arg(long, default_value_t = 10_000)]
+    size: usize,
+    /// Number of symbols the linear scan is timed on; the full run is extrapolated
+    #[arg(long, default_value_t = 200)]
+    sample: usize,
+    /// Seed for the random generator
+    #[arg(long, default_value_t = 2023)]
+    seed: u64,
+}
+
+const SYMBOLS: &[u8] = b"*#+$/@%&=-";
+
+/// A square schematic of roughly 60% empty cells, with numbers of up to three
+/// digits and symbols spread over the rest.
+fn generate(size: usize, seed: u64) -> String {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut text = String::with_capacity(size * (size + 1));
+    for _ in 0..size {
+        let mut column = 0;
+        while column < size {
+            match rng.gen_range(0..10) {
+                0..=5 => {
+                    text.push('.');
+                    column += 1;
+                }
+                6..=8 => {
+                    let digits = rng.gen_range(1..=3).min(size - column);
+                    for _ in 0..digits {
+                        text.push(char::from(b'0' + rng.gen_range(0..10)));
+                    }
+                    column += digits;
+                    if column < size {
+                        text.push('.');
+                        column += 1;
+                    }
+                }
+                _ => {
+                    text.push(char::from(SYMBOLS[rng.gen_range(0..SYMBOLS.len())]));
+                    column += 1;
+                }
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn adjacent_sum(parts: Vec<&Part>) -> u64 {
+    parts.iter().map(|part| u64::from(part.number)).sum()
+}
+
+pub fn run(args: &BenchArgs) -> Result<(), Error> {
+    let start = Instant::now();
+    let text = generate(args.size, args.seed);
+    let (grid, parts, symbols) = parse(text.as_bytes(), Adjacency::Eight)?;
+    println!(
+        "Generated {0}x{0} schematic with {1} parts and {2} symbols in {3:.2?}",
+        args.size,
+        parts.len(),
+        symbols.len(),
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let index = PartIndex::new(&grid, &parts);
+    let total: u64 = symbols
+        .iter()
+        .map(|symbol| adjacent_sum(symbol.adjacent_parts(&index)))
+        .sum();
+    let indexed_time = start.elapsed();
+    println!(
+        "Indexed: all {} symbols in {indexed_time:.2?} (sum {total})",
+        symbols.len()
+    );
+
+    let sample = &symbols[..args.sample.min(symbols.len())];
+    let start = Instant::now();
+    let linear: u64 = sample
+        .iter()
+        .map(|symbol| adjacent_sum(symbol.scan_adjacent_parts(&parts)))
+        .sum();
+    let linear_time = start.elapsed();
+    let expected: u64 = sample
+        .iter()
+        .map(|symbol| adjacent_sum(symbol.adjacent_parts(&index)))
+        .sum();
+    assert_eq!(linear, expected, "index and linear scan disagree");
+    let extrapolated = linear_time.mul_f64(symbols.len() as f64 / sample.len().max(1) as f64);
+    println!(
+        "Linear: {} symbols in {linear_time:.2?}, about {extrapolated:.2?} for all ({:.0}x slower)",
+        sample.len(),
+        extrapolated.as_secs_f64() / indexed_time.as_secs_f64()
+    );
+    Ok(())
+}
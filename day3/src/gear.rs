@@ -0,0 +1,138 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use clap::{Args, ValueEnum};
+
+use crate::{Part, Symbol};
+
+#[derive(Debug)]
+pub struct Gear {
+    pub row: usize,
+    pub column: usize,
+    pub ratio: u64,
+}
+
+impl Gear {
+    /// Adds the ratio to the running sum of gear ratios.
+    pub fn add_to(&self, sum: u64) -> Result<u64, GearOverflow> {
+        sum.checked_add(self.ratio).ok_or(GearOverflow {
+            row: self.row,
+            column: self.column,
+        })
+    }
+}
+
+/// A gear ratio, or the sum of the ratios up to a gear, that doesn't fit in a
+/// `u64`. Row and column are 0-based, like everywhere else internally.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GearOverflow {
+    pub row: usize,
+    pub column: usize,
+}
+
+impl Display for GearOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gear ratios don't fit in 64 bits at the gear in row {}, column {}",
+            self.row + 1,
+            self.column + 1
+        )
+    }
+}
+
+impl std::error::Error for GearOverflow {}
+
+/// How many adjacent parts a gear needs: `2` for exactly two, `2+` for at
+/// least two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    fn accepts(self, count: usize) -> bool {
+        match self {
+            Arity::Exactly(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+        }
+    }
+}
+
+impl FromStr for Arity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, at_least) = match s.strip_suffix('+') {
+            Some(number) => (number, true),
+            None => (s, false),
+        };
+        let n = number
+            .trim()
+            .parse()
+            .map_err(|_| format!("expected a number of parts like `2` or `2+`, got `{s}`"))?;
+        if n == 0 {
+            return Err("a gear needs at least one part".to_string());
+        }
+        Ok(if at_least {
+            Arity::AtLeast(n)
+        } else {
+            Arity::Exactly(n)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Combine {
+    /// Multiply the adjacent part numbers
+    Product,
+    /// Add the adjacent part numbers
+    Sum,
+}
+
+// What counts as a gear, and how its ratio is computed. Not a doc comment,
+// clap would take it for the program's about text.
+#[derive(Args, Debug)]
+pub struct GearRule {
+    /// Symbols that can be gears
+    #[arg(long, default_value = "*")]
+    gear_symbols: String,
+    /// Adjacent parts a gear needs, `N` for exactly N or `N+` for at least N
+    #[arg(long, default_value = "2")]
+    gear_parts: Arity,
+    /// How the numbers of a gear's parts make up its ratio
+    #[arg(long, value_enum, default_value_t = Combine::Product)]
+    combine: Combine,
+}
+
+impl GearRule {
+    pub fn is_candidate(&self, symbol: &Symbol) -> bool {
+        self.gear_symbols.contains(symbol.symbol)
+    }
+
+    /// `symbol` as a gear, or `None` if it isn't one.
+    pub fn gear(&self, symbol: &Symbol, parts: &[&Part]) -> Result<Option<Gear>, GearOverflow> {
+        if !self.is_candidate(symbol) || !self.gear_parts.accepts(parts.len()) {
+            return Ok(None);
+        }
+        let mut numbers = parts.iter().map(|part| u64::from(part.number));
+        let ratio = match self.combine {
+            Combine::Product => numbers.try_fold(1, u64::checked_mul),
+            Combine::Sum => numbers.try_fold(0, u64::checked_add),
+        };
+        let Some(ratio) = ratio else {
+            return Err(GearOverflow {
+                row: symbol.row,
+                column: symbol.column,
+            });
+        };
+        Ok(Some(Gear {
+            row: symbol.row,
+            column: symbol.column,
+            ratio,
+        }))
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{gear::Gear, index::PartIndex, Part, Symbol};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Graphviz DOT
+    Dot,
+    /// Lists of symbols, parts and edges
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct SymbolNode {
+    id: usize,
+    symbol: char,
+    row: usize,
+    column: usize,
+    /// Only set for gears
+    ratio: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct PartNode {
+    id: usize,
+    number: u32,
+    row: usize,
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Edge {
+    symbol: usize,
+    part: usize,
+}
+
+/// The bipartite graph of symbols and the parts next to them. Every symbol
+/// and every part is a node, even without any edges. Rows and columns count
+/// from 1, and `end` is the last column of a part.
+#[derive(Debug, Serialize)]
+pub struct Graph {
+    symbols: Vec<SymbolNode>,
+    parts: Vec<PartNode>,
+    edges: Vec<Edge>,
+}
+
+impl Graph {
+    pub fn new(parts: &[Part], symbols: &[Symbol], index: &PartIndex, gears: &[Gear]) -> Self {
+        // parts are sorted by position, so that's enough to find their IDs
+        let part_id = |part: &Part| {
+            parts
+                .binary_search_by_key(&(part.row, part.range.start), |part| {
+                    (part.row, part.range.start)
+                })
+                .expect("adjacent parts come from the same list")
+        };
+
+        let mut edges = Vec::new();
+        let mut gears = gears.iter().peekable();
+        let symbols = symbols
+            .iter()
+            .enumerate()
+            .map(|(id, symbol)| {
+                for part in symbol.adjacent_parts(index) {
+                    edges.push(Edge {
+                        symbol: id,
+                        part: part_id(part),
+                    });
+                }
+                // gears are a subsequence of the symbols
+                let ratio = gears
+                    .next_if(|gear| (gear.row, gear.column) == (symbol.row, symbol.column))
+                    .map(|gear| gear.ratio);
+                SymbolNode {
+                    id,
+                    symbol: symbol.symbol,
+                    row: symbol.row + 1,
+                    column: symbol.column + 1,
+                    ratio,
+                }
+            })
+            .collect();
+        let parts = parts
+            .iter()
+            .enumerate()
+            .map(|(id, part)| PartNode {
+                id,
+                number: part.number,
+                row: part.row + 1,
+                start: part.range.start + 1,
+                end: part.range.end,
+            })
+            .collect();
+
+        Self {
+            symbols,
+            parts,
+            edges,
+        }
+    }
+
+    pub fn write(&self, out: &mut impl Write, format: Format) -> io::Result<()> {
+        match format {
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut *out, self)?;
+                writeln!(out)
+            }
+            Format::Dot => {
+                writeln!(out, "graph schematic {{")?;
+                writeln!(out, "  node [fontname=monospace];")?;
+                for symbol in &self.symbols {
+                    let label = format!("{} ({}, {})", symbol.symbol, symbol.row, symbol.column);
+                    match symbol.ratio {
+                        Some(ratio) => writeln!(
+                            out,
+                            "  s{} [shape=box, style=filled, fillcolor=gold, label={:?}];",
+                            symbol.id,
+                            format!("{label}\nratio {ratio}")
+                        )?,
+                        None => writeln!(out, "  s{} [shape=box, label={label:?}];", symbol.id)?,
+                    }
+                }
+                for part in &self.parts {
+                    writeln!(
+                        out,
+                        "  p{} [shape=ellipse, label=\"{} ({}, {}-{})\"];",
+                        part.id, part.number, part.row, part.start, part.end
+                    )?;
+                }
+                for edge in &self.edges {
+                    writeln!(out, "  s{} -- p{};", edge.symbol, edge.part)?;
+                }
+                writeln!(out, "}}")
+            }
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use crate::Part;
+
+/// Which cells around a symbol count as adjacent: `8` for the surrounding
+/// cells including diagonals, `4` for only the ones sharing an edge, and
+/// `radius:N` for everything at most N rows and N columns away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adjacency {
+    Four,
+    Eight,
+    Radius(usize),
+}
+
+impl Adjacency {
+    /// How many rows (and columns) away a neighbour can be.
+    pub fn reach(self) -> usize {
+        match self {
+            Adjacency::Four | Adjacency::Eight => 1,
+            Adjacency::Radius(n) => n,
+        }
+    }
+
+    /// Whether a cell `rows` rows and `columns` columns away is a neighbour.
+    fn is_neighbour(self, rows: usize, columns: usize) -> bool {
+        match self {
+            Adjacency::Four => rows + columns == 1,
+            Adjacency::Eight => rows.max(columns) == 1,
+            Adjacency::Radius(n) => (1..=n).contains(&rows.max(columns)),
+        }
+    }
+
+    /// Whether any cell of `part` is a neighbour of the cell at `row`, `column`.
+    pub fn touches(self, part: &Part, row: usize, column: usize) -> bool {
+        let columns = if column < part.range.start {
+            part.range.start - column
+        } else if column >= part.range.end {
+            column + 1 - part.range.end
+        } else {
+            0
+        };
+        self.is_neighbour(part.row.abs_diff(row), columns)
+    }
+}
+
+impl FromStr for Adjacency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "4" => Ok(Adjacency::Four),
+            "8" => Ok(Adjacency::Eight),
+            _ => match s.strip_prefix("radius:").map(str::parse) {
+                Some(Ok(n)) if n > 0 => Ok(Adjacency::Radius(n)),
+                _ => Err(format!(
+                    "expected `4`, `8` or `radius:N` with N at least 1, got `{s}`"
+                )),
+            },
+        }
+    }
+}
+
+/// A line that isn't as wide as the first one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaggedLine {
+    pub line: usize,
+    pub width: usize,
+    pub expected: usize,
+}
+
+impl Display for RaggedLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {} is {} characters wide, expected {} like the first line",
+            self.line, self.width, self.expected
+        )
+    }
+}
+
+impl std::error::Error for RaggedLine {}
+
+impl RaggedLine {
+    /// Checks the 1-based `line` of `width` characters against the width of
+    /// the first line, which is `None` while reading that first line.
+    pub fn check(line: usize, width: usize, first: Option<usize>) -> Result<(), RaggedLine> {
+        match first {
+            Some(expected) if width != expected => Err(RaggedLine {
+                line,
+                width,
+                expected,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The schematic as a rectangle of cells. Every access is bounds-checked, so
+/// looking around a symbol on the border just finds fewer neighbours.
+#[derive(Debug)]
+pub struct Grid {
+    cells: Vec<char>,
+    width: usize,
+    height: usize,
+    adjacency: Adjacency,
+}
+
+impl Grid {
+    pub fn new(adjacency: Adjacency) -> Self {
+        Self {
+            cells: Vec::new(),
+            width: 0,
+            height: 0,
+            adjacency,
+        }
+    }
+
+    /// Appends a row, which must be as wide as the first one.
+    pub fn push_row(&mut self, text: &str) -> Result<&[char], RaggedLine> {
+        let width = text.chars().count();
+        let first = (self.height > 0).then_some(self.width);
+        RaggedLine::check(self.height + 1, width, first)?;
+        let start = self.cells.len();
+        self.cells.extend(text.chars());
+        self.width = width;
+        self.height += 1;
+        Ok(&self.cells[start..])
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<char> {
+        if row < self.height && column < self.width {
+            Some(self.cells[row * self.width + column])
+        } else {
+            None
+        }
+    }
+
+    /// The cells next to `row`, `column` that are on the grid, row by row.
+    pub fn neighbours(&self, row: usize, column: usize) -> impl Iterator<Item = (usize, usize)> {
+        let adjacency = self.adjacency;
+        let reach = adjacency.reach();
+        let rows = row.saturating_sub(reach)..(row.saturating_add(reach + 1)).min(self.height);
+        let columns =
+            column.saturating_sub(reach)..(column.saturating_add(reach + 1)).min(self.width);
+        rows.flat_map(move |r| columns.clone().map(move |c| (r, c)))
+            .filter(move |&(r, c)| adjacency.is_neighbour(r.abs_diff(row), c.abs_diff(column)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEIGHT: usize = 5;
+    const WIDTH: usize = 6;
+
+    fn grid(adjacency: Adjacency) -> Grid {
+        let mut grid = Grid::new(adjacency);
+        for _ in 0..HEIGHT {
+            grid.push_row(&".".repeat(WIDTH)).unwrap();
+        }
+        grid
+    }
+
+    /// The neighbours worked out from offsets with signed arithmetic, as a
+    /// reference for the bounds checks in `Grid::neighbours`.
+    fn expected(adjacency: Adjacency, row: usize, column: usize) -> Vec<(usize, usize)> {
+        let reach = adjacency.reach() as i64;
+        let mut cells = Vec::new();
+        for dr in -reach..=reach {
+            for dc in -reach..=reach {
+                let neighbour = match adjacency {
+                    Adjacency::Four => dr.abs() + dc.abs() == 1,
+                    Adjacency::Eight | Adjacency::Radius(_) => (dr, dc) != (0, 0),
+                };
+                let (r, c) = (row as i64 + dr, column as i64 + dc);
+                let inside = (0..HEIGHT as i64).contains(&r) && (0..WIDTH as i64).contains(&c);
+                if neighbour && inside {
+                    cells.push((r as usize, c as usize));
+                }
+            }
+        }
+        cells
+    }
+
+    const ADJACENCIES: [Adjacency; 5] = [
+        Adjacency::Four,
+        Adjacency::Eight,
+        Adjacency::Radius(1),
+        Adjacency::Radius(2),
+        Adjacency::Radius(7),
+    ];
+
+    #[test]
+    fn neighbours_on_every_cell() {
+        for adjacency in ADJACENCIES {
+            let grid = grid(adjacency);
+            for row in 0..HEIGHT {
+                for column in 0..WIDTH {
+                    let found: Vec<_> = grid.neighbours(row, column).collect();
+                    assert_eq!(
+                        found,
+                        expected(adjacency, row, column),
+                        "{adjacency:?} at {row},{column}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighbours_in_corners() {
+        let corners = [
+            (0, 0),
+            (0, WIDTH - 1),
+            (HEIGHT - 1, 0),
+            (HEIGHT - 1, WIDTH - 1),
+        ];
+        for (adjacency, count) in [
+            (Adjacency::Four, 2),
+            (Adjacency::Eight, 3),
+            (Adjacency::Radius(2), 8),
+        ] {
+            let grid = grid(adjacency);
+            for (row, column) in corners {
+                assert_eq!(
+                    grid.neighbours(row, column).count(),
+                    count,
+                    "{adjacency:?} at {row},{column}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn neighbours_on_edges() {
+        let edges = [(0, 2), (HEIGHT - 1, 2), (2, 0), (2, WIDTH - 1)];
+        for (adjacency, count) in [
+            (Adjacency::Four, 3),
+            (Adjacency::Eight, 5),
+            (Adjacency::Radius(2), 14),
+        ] {
+            let grid = grid(adjacency);
+            for (row, column) in edges {
+                assert_eq!(
+                    grid.neighbours(row, column).count(),
+                    count,
+                    "{adjacency:?} at {row},{column}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn single_cell_has_no_neighbours() {
+        for adjacency in ADJACENCIES {
+            let mut grid = Grid::new(adjacency);
+            grid.push_row("*").unwrap();
+            assert_eq!(grid.neighbours(0, 0).count(), 0);
+        }
+    }
+
+    #[test]
+    fn touches_agrees_with_neighbours() {
+        for adjacency in ADJACENCIES {
+            let grid = grid(adjacency);
+            for row in 0..HEIGHT {
+                for column in 0..WIDTH {
+                    let neighbours: Vec<_> = grid.neighbours(row, column).collect();
+                    for (r, c) in (0..HEIGHT).flat_map(|r| (0..WIDTH).map(move |c| (r, c))) {
+                        let part = Part {
+                            number: 1,
+                            row: r,
+                            range: c..c + 1,
+                        };
+                        assert_eq!(
+                            adjacency.touches(&part, row, column),
+                            neighbours.contains(&(r, c)),
+                            "{adjacency:?} at {row},{column} with a part at {r},{c}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn get_is_bounds_checked() {
+        let grid = grid(Adjacency::Eight);
+        assert_eq!(grid.get(0, 0), Some('.'));
+        assert_eq!(grid.get(HEIGHT - 1, WIDTH - 1), Some('.'));
+        assert_eq!(grid.get(HEIGHT, 0), None);
+        assert_eq!(grid.get(0, WIDTH), None);
+        assert_eq!(grid.get(usize::MAX, usize::MAX), None);
+    }
+
+    #[test]
+    fn ragged_lines_are_rejected() {
+        let mut grid = Grid::new(Adjacency::Eight);
+        grid.push_row("...").unwrap();
+        assert_eq!(
+            grid.push_row(".."),
+            Err(RaggedLine {
+                line: 2,
+                width: 2,
+                expected: 3,
+            })
+        );
+        assert_eq!(
+            grid.push_row("....").unwrap_err(),
+            RaggedLine {
+                line: 2,
+                width: 4,
+                expected: 3,
+            }
+        );
+        // the rejected rows are not kept
+        assert_eq!(grid.push_row("*.*"), Ok(&['*', '.', '*'][..]));
+        assert_eq!(grid.get(1, 0), Some('*'));
+    }
+
+    #[test]
+    fn parse_adjacency() {
+        assert_eq!("4".parse(), Ok(Adjacency::Four));
+        assert_eq!("8".parse(), Ok(Adjacency::Eight));
+        assert_eq!("radius:3".parse(), Ok(Adjacency::Radius(3)));
+        for junk in [
+            "radius:0",
+            "radius:",
+            "radius:-1",
+            "radius:x",
+            "radius",
+            "",
+            "6",
+            "r3",
+        ] {
+            assert!(junk.parse::<Adjacency>().is_err(), "{junk:?} was accepted");
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::ops::RangeInclusive;
+
+use crate::{grid::Grid, Part};
+
+/// Parts grouped by row, each row sorted by column, so looking up the parts
+/// touching a few columns is a binary search instead of a scan over all parts.
+pub struct PartIndex<'a> {
+    grid: &'a Grid,
+    parts: &'a [Part],
+    /// `parts[row_start[row]..row_start[row + 1]]` are the parts in `row`.
+    row_start: Vec<usize>,
+}
+
+impl<'a> PartIndex<'a> {
+    /// `parts` must be sorted by row and column, which is the order they are
+    /// found in while reading the schematic.
+    pub fn new(grid: &'a Grid, parts: &'a [Part]) -> Self {
+        let rows = parts.last().map_or(0, |part| part.row + 1);
+        let mut row_start = Vec::with_capacity(rows + 1);
+        let mut idx = 0;
+        for row in 0..=rows {
+            while idx < parts.len() && parts[idx].row < row {
+                idx += 1;
+            }
+            row_start.push(idx);
+        }
+        Self {
+            grid,
+            parts,
+            row_start,
+        }
+    }
+
+    /// The grid the parts were found on.
+    pub fn grid(&self) -> &'a Grid {
+        self.grid
+    }
+
+    /// Parts in `row` that overlap `columns`.
+    pub fn find(&self, row: usize, columns: RangeInclusive<usize>) -> &'a [Part] {
+        if row + 1 >= self.row_start.len() {
+            return &[];
+        }
+        let in_row = &self.parts[self.row_start[row]..self.row_start[row + 1]];
+        let first = in_row.partition_point(|part| part.range.end <= *columns.start());
+        let last = in_row.partition_point(|part| part.range.start <= *columns.end());
+        &in_row[first..last.max(first)]
+    }
+}
@@ -1,17 +1,122 @@
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
+    collections::HashSet,
+    fmt::{self, Display},
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
     ops::Range,
     path::PathBuf,
+    process::ExitCode,
 };
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+
+mod bench;
+mod gear;
+mod graph;
+mod grid;
+mod index;
+mod render;
+mod stream;
+
+use bench::BenchArgs;
+use gear::{Gear, GearOverflow, GearRule};
+use graph::Graph;
+use grid::{Adjacency, Grid, RaggedLine};
+use index::PartIndex;
+use render::{render, Format, Highlights};
 
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    filename: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(required = true)]
+    filename: Option<PathBuf>,
+    /// Only compute the answer for one half of the puzzle (both are printed otherwise)
+    #[arg(long, value_enum)]
+    part: Option<PuzzlePart>,
+    /// Which cells count as adjacent: `8` (including diagonals), `4` (sharing
+    /// an edge) or `radius:N` (at most N rows and N columns away)
+    #[arg(long, default_value = "8")]
+    adjacency: Adjacency,
+    #[command(flatten)]
+    gear: GearRule,
+    /// Print the schematic with parts, orphan numbers and gears highlighted
+    /// instead of the answers
+    #[arg(long, value_enum, num_args = 0..=1, require_equals = true, default_missing_value = "ansi")]
+    render: Option<Format>,
+    /// Read the schematic a few rows at a time, printing orphan numbers and
+    /// gears as soon as they are known, for schematics that don't fit in memory
+    #[arg(long, conflicts_with = "render")]
+    stream: bool,
+    /// Print the graph of symbols and their adjacent parts instead of the answers
+    #[arg(long, value_enum, conflicts_with_all = ["render", "stream"])]
+    graph: Option<graph::Format>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Time the part index against scanning all parts on a generated schematic
+    Bench(BenchArgs),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum PuzzlePart {
+    /// Sum of all numbers adjacent to a symbol
+    #[value(name = "1")]
+    One,
+    /// Sum of the gear ratios
+    #[value(name = "2")]
+    Two,
 }
 
+#[derive(Debug)]
+enum Error {
+    Io(io::Error),
+    Ragged(RaggedLine),
+    Gear(GearOverflow),
+    NumberTooLarge {
+        row: usize,
+        column: usize,
+    },
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<RaggedLine> for Error {
+    fn from(err: RaggedLine) -> Self {
+        Error::Ragged(err)
+    }
+}
+
+impl From<GearOverflow> for Error {
+    fn from(err: GearOverflow) -> Self {
+        Error::Gear(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => err.fmt(f),
+            Error::Ragged(err) => err.fmt(f),
+            Error::Gear(err) => err.fmt(f),
+            Error::NumberTooLarge { row, column } => write!(
+                f,
+                "the number at row {}, column {} doesn't fit in 32 bits",
+                row + 1,
+                column + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 #[derive(Debug)]
 struct Part {
     number: u32,
@@ -19,88 +124,227 @@ struct Part {
     range: Range<usize>,
 }
 
+impl Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (row {}, column {})",
+            self.number,
+            self.row + 1,
+            self.range.start + 1
+        )
+    }
+}
+
 #[derive(Debug)]
-struct Gear {
+struct Symbol {
+    symbol: char,
     row: usize,
     column: usize,
 }
 
+impl Symbol {
+    fn adjacent_parts<'a>(&self, index: &PartIndex<'a>) -> Vec<&'a Part> {
+        let grid = index.grid();
+        let mut adjacent_parts: Vec<&Part> = Vec::new();
+
+        for (row, column) in grid.neighbours(self.row, self.column) {
+            if !grid.get(row, column).is_some_and(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            // a part covers several cells, but only counts once
+            for part in index.find(row, column..=column) {
+                if !adjacent_parts.iter().any(|seen| std::ptr::eq(*seen, part)) {
+                    adjacent_parts.push(part);
+                }
+            }
+        }
+
+        adjacent_parts
+    }
+
+    /// Same as [`Symbol::adjacent_parts`] with the default 8-neighbour
+    /// adjacency, but scanning all parts for every row. Only kept around to
+    /// compare against in the benchmark.
+    fn scan_adjacent_parts<'a>(&self, parts: &'a [Part]) -> Vec<&'a Part> {
+        let mut adjacent_parts = Vec::new();
+
+        if self.row > 0 {
+            adjacent_parts.extend(find_parts(self.row - 1, self.column, parts));
+        }
+        adjacent_parts.extend(find_parts(self.row, self.column, parts));
+        adjacent_parts.extend(find_parts(self.row + 1, self.column, parts));
+
+        adjacent_parts
+    }
+}
+
 fn find_parts(row: usize, column: usize, parts: &[Part]) -> Vec<&Part> {
     parts
         .iter()
         .filter(|&part| {
             part.row == row
-                && (part.range.contains(&(column - 1))
+                && (part.range.contains(&(column.saturating_sub(1)))
                     || part.range.contains(&(column))
                     || part.range.contains(&(column + 1)))
         })
         .collect()
 }
 
-fn main() -> std::io::Result<()> {
-    let args = Args::parse();
-
-    let file = File::open(args.filename)?;
-    let reader = BufReader::new(file);
+/// Finds the parts and symbols in one row of the schematic.
+fn parse_row(row: usize, cells: &[char]) -> Result<(Vec<Part>, Vec<Symbol>), Error> {
+    let mut parts = Vec::new();
+    let mut symbols = Vec::new();
+    let mut partno: u32 = 0;
+    let mut part_start = None;
+    for (column, &c) in cells.iter().enumerate() {
+        if let Some(digit) = c.to_digit(10) {
+            let start = *part_start.get_or_insert(column);
+            partno = partno
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(digit))
+                .ok_or(Error::NumberTooLarge { row, column: start })?;
+        } else {
+            if let Some(start) = part_start {
+                parts.push(Part {
+                    number: partno,
+                    row,
+                    range: start..column,
+                });
+                partno = 0;
+                part_start = None;
+            }
+            if c != '.' {
+                symbols.push(Symbol {
+                    symbol: c,
+                    row,
+                    column,
+                });
+            }
+        }
+    }
+    if let Some(start) = part_start {
+        parts.push(Part {
+            number: partno,
+            row,
+            range: start..cells.len(),
+        });
+    }
+    Ok((parts, symbols))
+}
 
+/// Reads a schematic, returning it as a grid along with its parts and symbols
+/// in reading order.
+fn parse(
+    reader: impl BufRead,
+    adjacency: Adjacency,
+) -> Result<(Grid, Vec<Part>, Vec<Symbol>), Error> {
+    let mut grid = Grid::new(adjacency);
     let mut parts = Vec::new();
-    let mut gears = Vec::new();
+    let mut symbols = Vec::new();
 
     for (row, text) in reader.lines().enumerate() {
-        let text = text?;
-        let mut partno = 0;
-        let mut part_start = None;
-        let len = text.len();
-        for (column, c) in text.chars().enumerate() {
-            if let Some(digit) = c.to_digit(10) {
-                partno = partno * 10 + digit;
-                if part_start.is_none() {
-                    part_start = Some(column);
-                }
-            } else {
-                if let Some(start) = part_start {
-                    parts.push(Part {
-                        number: partno,
-                        row,
-                        range: start..column,
-                    });
-                    partno = 0;
-                    part_start = None;
-                }
-                if c == '*' {
-                    gears.push(Gear { row, column });
-                }
-            }
+        let (row_parts, row_symbols) = parse_row(row, grid.push_row(&text?)?)?;
+        parts.extend(row_parts);
+        symbols.extend(row_symbols);
+    }
+
+    Ok((grid, parts, symbols))
+}
+
+fn print_answers(part: Option<PuzzlePart>, part_sum: u64, gear_sum: u64) {
+    match part {
+        Some(PuzzlePart::One) => println!("{part_sum}"),
+        Some(PuzzlePart::Two) => println!("{gear_sum}"),
+        None => {
+            println!("Part 1: {part_sum}");
+            println!("Part 2: {gear_sum}");
         }
-        if let Some(start) = part_start {
-            parts.push(Part {
-                number: partno,
-                row,
-                range: start..len,
-            });
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
         }
     }
+}
 
-    let sum: u32 = gears
-        .into_iter()
-        .map(|symbol| {
-            let mut adjacent_parts = Vec::new();
+fn run(args: Args) -> Result<(), Error> {
+    if let Some(Command::Bench(bench_args)) = &args.command {
+        return bench::run(bench_args);
+    }
 
-            if symbol.row > 0 {
-                adjacent_parts.extend(find_parts(symbol.row - 1, symbol.column, &parts));
-            }
-            adjacent_parts.extend(find_parts(symbol.row, symbol.column, &parts));
-            adjacent_parts.extend(find_parts(symbol.row + 1, symbol.column, &parts));
+    if args.stream {
+        let file = File::open(args.filename.as_ref().unwrap())?;
+        let mut out = io::stdout().lock();
+        let (part_sum, gear_sum) = stream::stream(
+            BufReader::new(file),
+            args.adjacency,
+            &args.gear,
+            args.part,
+            &mut out,
+        )?;
+        print_answers(args.part, part_sum, gear_sum);
+        return Ok(());
+    }
 
-            if adjacent_parts.len() == 2 {
-                adjacent_parts[0].number * adjacent_parts[1].number
-            } else {
-                0
-            }
-        })
+    let text = fs::read_to_string(args.filename.as_ref().unwrap())?;
+    let lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let (grid, parts, symbols) = parse(text.as_bytes(), args.adjacency)?;
+    let index = PartIndex::new(&grid, &parts);
+
+    // a part is identified by where it starts
+    let valid: HashSet<(usize, usize)> = symbols
+        .iter()
+        .flat_map(|symbol| symbol.adjacent_parts(&index))
+        .map(|part| (part.row, part.range.start))
+        .collect();
+    let is_valid = |part: &Part| valid.contains(&(part.row, part.range.start));
+    let part_sum: u64 = parts
+        .iter()
+        .filter(|part| is_valid(part))
+        .map(|part| u64::from(part.number))
         .sum();
 
-    println!("{sum}");
+    let gears: Vec<Gear> = symbols
+        .iter()
+        .filter_map(|symbol| {
+            args.gear
+                .gear(symbol, &symbol.adjacent_parts(&index))
+                .transpose()
+        })
+        .collect::<Result<_, _>>()?;
+    let gear_sum = gears.iter().try_fold(0, |sum, gear| gear.add_to(sum))?;
+
+    if let Some(format) = args.graph {
+        let graph = Graph::new(&parts, &symbols, &index, &gears);
+        graph.write(&mut io::stdout().lock(), format)?;
+        return Ok(());
+    }
+    if let Some(format) = args.render {
+        let highlights = Highlights {
+            parts: &parts,
+            is_valid: &is_valid,
+            symbols: &symbols,
+            is_candidate: &|symbol| args.gear.is_candidate(symbol),
+            gears: &gears,
+        };
+        render(&mut io::stdout().lock(), format, &lines, &highlights)?;
+        return Ok(());
+    }
+
+    // one line per orphan, the same as --stream, which can't collect them
+    if args.part != Some(PuzzlePart::Two) {
+        for part in parts.iter().filter(|part| !is_valid(part)) {
+            println!("Orphan number: {part}");
+        }
+    }
+
+    print_answers(args.part, part_sum, gear_sum);
 
     Ok(())
 }
@@ -0,0 +1,202 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use clap::ValueEnum;
+
+use crate::{gear::Gear, Part, Symbol};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Coloured text for the terminal
+    Ansi,
+    /// A standalone HTML page
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Part,
+    Orphan,
+    Gear,
+    NotGear,
+    Symbol,
+}
+
+impl Style {
+    fn ansi(self) -> &'static str {
+        match self {
+            Style::Part => "\x1b[32m",
+            Style::Orphan => "\x1b[31m",
+            Style::Gear => "\x1b[1;33m",
+            Style::NotGear => "\x1b[35m",
+            Style::Symbol => "\x1b[1m",
+        }
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Style::Part => "part",
+            Style::Orphan => "orphan",
+            Style::Gear => "gear",
+            Style::NotGear => "not-gear",
+            Style::Symbol => "symbol",
+        }
+    }
+}
+
+const STYLESHEET: &str = "\
+body { background: #1e1e1e; color: #777; }
+pre { font-size: 14px; line-height: 1.2; }
+.part { color: #6c6; }
+.orphan { color: #e55; }
+.gear { color: #fc3; font-weight: bold; }
+.not-gear { color: #c6c; }
+.symbol { color: #eee; font-weight: bold; }
+.ratio { color: #fc3; }
+";
+
+/// Everything that gets highlighted in the schematic.
+pub struct Highlights<'a> {
+    pub parts: &'a [Part],
+    pub is_valid: &'a dyn Fn(&Part) -> bool,
+    pub symbols: &'a [Symbol],
+    pub is_candidate: &'a dyn Fn(&Symbol) -> bool,
+    pub gears: &'a [Gear],
+}
+
+impl Highlights<'_> {
+    fn styles(&self, lines: &[String]) -> Vec<Vec<Option<Style>>> {
+        let mut styles: Vec<Vec<Option<Style>>> = lines
+            .iter()
+            .map(|line| vec![None; line.chars().count()])
+            .collect();
+        let mut set = |row: usize, column: usize, style| {
+            if let Some(cell) = styles.get_mut(row).and_then(|row| row.get_mut(column)) {
+                *cell = Some(style);
+            }
+        };
+
+        for part in self.parts {
+            let style = if (self.is_valid)(part) {
+                Style::Part
+            } else {
+                Style::Orphan
+            };
+            for column in part.range.clone() {
+                set(part.row, column, style);
+            }
+        }
+        for symbol in self.symbols {
+            let style = if (self.is_candidate)(symbol) {
+                Style::NotGear
+            } else {
+                Style::Symbol
+            };
+            set(symbol.row, symbol.column, style);
+        }
+        for gear in self.gears {
+            set(gear.row, gear.column, Style::Gear);
+        }
+        styles
+    }
+
+    /// The gears grouped by row, each row in column order.
+    fn gears_by_row(&self) -> BTreeMap<usize, Vec<&Gear>> {
+        let mut rows: BTreeMap<usize, Vec<&Gear>> = BTreeMap::new();
+        for gear in self.gears {
+            rows.entry(gear.row).or_default().push(gear);
+        }
+        rows
+    }
+}
+
+/// Runs of characters sharing a style. Every gear is a run of its own.
+fn runs<'a>(line: &'a str, styles: &'a [Option<Style>]) -> Vec<(Option<Style>, String)> {
+    let mut runs: Vec<(Option<Style>, String)> = Vec::new();
+    for (c, style) in line.chars().zip(styles) {
+        match runs.last_mut() {
+            Some((last, text)) if last == style && *style != Some(Style::Gear) => text.push(c),
+            _ => runs.push((*style, c.to_string())),
+        }
+    }
+    runs
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Prints the schematic back with valid parts, orphan numbers, gears and
+/// gear symbols that aren't gears each in their own colour. The ratios of the
+/// gears in a row are listed after it.
+pub fn render(
+    out: &mut impl Write,
+    format: Format,
+    lines: &[String],
+    highlights: &Highlights,
+) -> io::Result<()> {
+    let styles = highlights.styles(lines);
+    let gears_by_row = highlights.gears_by_row();
+    let gears_in = |row: usize| gears_by_row.get(&row).map_or(&[][..], Vec::as_slice);
+    match format {
+        Format::Ansi => {
+            for (row, line) in lines.iter().enumerate() {
+                for (style, text) in runs(line, &styles[row]) {
+                    match style {
+                        Some(style) => write!(out, "{}{text}\x1b[0m", style.ansi())?,
+                        None => write!(out, "{text}")?,
+                    }
+                }
+                let ratios: Vec<String> = gears_in(row)
+                    .iter()
+                    .map(|gear| format!("column {}: {}", gear.column + 1, gear.ratio))
+                    .collect();
+                if !ratios.is_empty() {
+                    write!(out, "  {}{}\x1b[0m", Style::Gear.ansi(), ratios.join(", "))?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Format::Html => {
+            writeln!(out, "<!DOCTYPE html>")?;
+            writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+            writeln!(out, "<title>Engine schematic</title>")?;
+            writeln!(out, "<style>\n{STYLESHEET}</style>\n</head>\n<body>\n<pre>")?;
+            for (row, line) in lines.iter().enumerate() {
+                // gear runs come in the same order as the gears of the row
+                let mut gears = gears_in(row).iter();
+                for (style, text) in runs(line, &styles[row]) {
+                    let text = escape(&text);
+                    match style {
+                        Some(Style::Gear) => {
+                            let ratio = gears.next().map_or(0, |gear| gear.ratio);
+                            write!(
+                                out,
+                                "<span class=\"gear\" title=\"gear ratio {ratio}\">{text}</span>"
+                            )?;
+                        }
+                        Some(style) => {
+                            write!(out, "<span class=\"{}\">{text}</span>", style.class())?
+                        }
+                        None => write!(out, "{text}")?,
+                    }
+                }
+                let ratios: Vec<String> = gears_in(row)
+                    .iter()
+                    .map(|gear| format!("column {}: {}", gear.column + 1, gear.ratio))
+                    .collect();
+                if !ratios.is_empty() {
+                    write!(out, "  <span class=\"ratio\">{}</span>", ratios.join(", "))?;
+                }
+                writeln!(out)?;
+            }
+            writeln!(out, "</pre>\n</body>\n</html>")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use std::{
+    collections::VecDeque,
+    io::{BufRead, Write},
+};
+
+use crate::{
+    gear::GearRule,
+    grid::{Adjacency, RaggedLine},
+    parse_row, Error, Part, PuzzlePart, Symbol,
+};
+
+#[derive(Default)]
+struct Row {
+    parts: Vec<Part>,
+    symbols: Vec<Symbol>,
+}
+
+/// Solves the schematic with only the rows within reach of the current one in
+/// memory (three with the default adjacency): everything about a row is known
+/// once the rows after it have been read. Orphan numbers and gears are written
+/// to `out` as soon as they are found (the orphans only if part 1 is shown,
+/// the gears only for part 2), and the sums are returned.
+pub fn stream(
+    reader: impl BufRead,
+    adjacency: Adjacency,
+    rule: &GearRule,
+    shown: Option<PuzzlePart>,
+    out: &mut impl Write,
+) -> Result<(u64, u64), Error> {
+    let reach = adjacency.reach();
+    let mut part_sum = 0;
+    let mut gear_sum = 0;
+    // `window[idx]` is the row being decided, the others are within reach of it
+    let mut decide = |window: &VecDeque<Row>, idx: usize| -> Result<(), Error> {
+        let current = &window[idx];
+        for part in &current.parts {
+            let valid = window.iter().any(|row| {
+                row.symbols
+                    .iter()
+                    .any(|symbol| adjacency.touches(part, symbol.row, symbol.column))
+            });
+            if valid {
+                part_sum += u64::from(part.number);
+            } else if shown != Some(PuzzlePart::Two) {
+                writeln!(out, "Orphan number: {part}")?;
+            }
+        }
+        for symbol in &current.symbols {
+            let adjacent_parts: Vec<&Part> = window
+                .iter()
+                .flat_map(|row| &row.parts)
+                .filter(|part| adjacency.touches(part, symbol.row, symbol.column))
+                .collect();
+            if let Some(gear) = rule.gear(symbol, &adjacent_parts)? {
+                gear_sum = gear.add_to(gear_sum)?;
+                if shown != Some(PuzzlePart::One) {
+                    writeln!(
+                        out,
+                        "Gear (row {}, column {}): {}",
+                        gear.row + 1,
+                        gear.column + 1,
+                        gear.ratio
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    };
+
+    let mut window: VecDeque<Row> = VecDeque::with_capacity(2 * reach + 2);
+    // index in `window` of the first row that hasn't been decided yet
+    let mut pending = 0;
+    let mut width = None;
+    for (row, text) in reader.lines().enumerate() {
+        let cells: Vec<char> = text?.chars().collect();
+        RaggedLine::check(row + 1, cells.len(), width)?;
+        width = Some(cells.len());
+        let (parts, symbols) = parse_row(row, &cells)?;
+        window.push_back(Row { parts, symbols });
+        if window.len() > pending + reach {
+            decide(&window, pending)?;
+            pending += 1;
+        }
+        if pending > reach {
+            window.pop_front();
+            pending -= 1;
+        }
+    }
+    while pending < window.len() {
+        decide(&window, pending)?;
+        pending += 1;
+    }
+    Ok((part_sum, gear_sum))
+}
@@ -0,0 +1,170 @@
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Card {
+    pub id: usize,
+    pub winning: Vec<usize>,
+    pub have: Vec<usize>,
+}
+
+impl Card {
+    /// Parses `Card 1: 41 48 83 | 83 86  6 31` on the 1-based line `line`.
+    ///
+    /// The card should be card `next_id`; whatever ID it has, `next_id` moves
+    /// on to the one after it, so a gap in the IDs is only reported once. The
+    /// number lists are checked even when the ID is wrong.
+    pub fn parse(line: usize, next_id: &mut usize, text: &str) -> Result<Self, Vec<ParseError>> {
+        let mut errors = Vec::new();
+        let mut cursor = Cursor { line, text, pos: 0 };
+        let card = cursor.card(next_id, &mut errors);
+        match card {
+            Ok(card) if errors.is_empty() => Ok(card),
+            Ok(_) => Err(errors),
+            Err(err) => {
+                errors.push(err);
+                Err(errors)
+            }
+        }
+    }
+
+    /// How many of the numbers we have are winning numbers.
+    pub fn matches(&self) -> usize {
+        self.have
+            .iter()
+            .filter(|number| self.winning.contains(number))
+            .count()
+    }
+}
+
+/// What's wrong with a line of the input, with 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Cursor<'a> {
+    line: usize,
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.text.len()
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.text[..self.pos].chars().count() + 1,
+            message: message.to_string(),
+        }
+    }
+
+    /// Skips whitespace, returning whether there was any.
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = self.rest();
+        let trimmed = rest.trim_start();
+        self.pos += rest.len() - trimmed.len();
+        rest.len() != trimmed.len()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{token}`")))
+        }
+    }
+
+    fn number(&mut self, what: &str) -> Result<usize, ParseError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(&format!("expected {what}")));
+        }
+        let number = rest[..len]
+            .parse()
+            .map_err(|_| self.error(&format!("{what} out of range")))?;
+        self.pos += len;
+        Ok(number)
+    }
+
+    /// At least one number, separated by whitespace and without duplicates,
+    /// along with the whitespace that follows the last one.
+    fn numbers(&mut self, what: &str) -> Result<Vec<usize>, ParseError> {
+        let mut numbers = Vec::new();
+        self.skip_whitespace();
+        loop {
+            let start = self.pos;
+            let number = self.number(what)?;
+            if numbers.contains(&number) {
+                self.pos = start;
+                return Err(self.error(&format!("{what} {number} appears twice")));
+            }
+            numbers.push(number);
+            let separated = self.skip_whitespace();
+            if !separated || !self.rest().starts_with(|c: char| c.is_ascii_digit()) {
+                return Ok(numbers);
+            }
+        }
+    }
+
+    /// A whole card. A wrong ID goes in `errors` rather than stopping the
+    /// parse, so the rest of the line still gets checked.
+    fn card(
+        &mut self,
+        next_id: &mut usize,
+        errors: &mut Vec<ParseError>,
+    ) -> Result<Card, ParseError> {
+        // a line too malformed to have an id still takes one up
+        let expected = *next_id;
+        *next_id += 1;
+        self.expect("Card")?;
+        if !self.skip_whitespace() {
+            return Err(self.error("expected whitespace"));
+        }
+        let start = self.pos;
+        let id = self.number("card id")?;
+        if id != expected {
+            let pos = std::mem::replace(&mut self.pos, start);
+            errors.push(self.error(&format!("expected card {expected}, found card {id}")));
+            self.pos = pos;
+        }
+        *next_id = id.saturating_add(1);
+        self.skip_whitespace();
+        self.expect(":")?;
+        let winning = self.numbers("winning number")?;
+        self.expect("|")?;
+        let have = self.numbers("number")?;
+        if !self.at_end() {
+            return Err(self.error("expected number or end of line"));
+        }
+        Ok(Card { id, winning, have })
+    }
+}
@@ -1,68 +1,230 @@
 use std::{
-    collections::HashSet,
+    fmt::{self, Display},
     fs::File,
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader},
     path::PathBuf,
+    process::ExitCode,
 };
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+
+mod card;
+
+use card::{Card, ParseError};
 
 #[derive(Parser, Debug)]
 struct Args {
     filename: PathBuf,
+    /// Only compute the answer for one part (both are printed otherwise)
+    #[arg(long, value_enum)]
+    part: Option<Part>,
+    /// What happens to copies won past the last card
+    #[arg(long, value_enum, default_value_t = Overflow::Error)]
+    overflow: Overflow,
 }
 
-#[derive(Default, Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Part {
+    /// Total points, each card being worth 2^(matches - 1)
+    #[value(name = "1")]
+    One,
+    /// Total number of cards after winning copies
+    #[value(name = "2")]
+    Two,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Overflow {
+    /// Drop the copies of cards that don't exist
+    Clamp,
+    /// Refuse to count, listing the cards that win too many copies
+    Error,
+    /// Carry on counting from the first card; copies of cards that were
+    /// already scored are counted, but don't win anything themselves
+    Wrap,
+}
+
+/// A card winning copies of more cards than there are after it.
+#[derive(Debug)]
+struct Overreach {
+    card: usize,
+    wins: usize,
+    remaining: usize,
+}
+
+impl Display for Overreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "card {} wins {} (only {} cards follow)",
+            self.card, self.wins, self.remaining
+        )
+    }
+}
+
+#[derive(Debug)]
+enum Error {
+    Io(io::Error),
+    Parse(Vec<ParseError>),
+    Overflow(Vec<Overreach>),
+    TooManyPoints { card: usize },
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => err.fmt(f),
+            Error::Parse(errors) => {
+                write!(f, "malformed cards:")?;
+                for err in errors {
+                    write!(f, "\n  {err}")?;
+                }
+                Ok(())
+            }
+            Error::TooManyPoints { card } => {
+                write!(
+                    f,
+                    "the points don't fit in {} bits from card {card} on",
+                    usize::BITS
+                )
+            }
+            Error::Overflow(overreaching) => {
+                write!(f, "cards win copies past the last card:")?;
+                for overreach in overreaching {
+                    write!(f, "\n  {overreach}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug)]
 struct Scratchcard {
+    id: usize,
     win_count: usize,
     card_count: usize,
 }
 
-fn main() -> std::io::Result<()> {
-    let args = Args::parse();
+/// Reads one card per line, skipping blank lines. Cards must be numbered
+/// 1, 2, 3, ... in order. Every malformed line is reported, not just the first.
+fn read_cards(reader: impl BufRead) -> Result<Vec<Card>, Error> {
+    let mut cards = Vec::new();
+    let mut errors = Vec::new();
+    let mut next_id = 1;
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Card::parse(idx + 1, &mut next_id, &line) {
+            Ok(card) => cards.push(card),
+            Err(errs) => errors.extend(errs),
+        }
+    }
+    if errors.is_empty() {
+        Ok(cards)
+    } else {
+        Err(Error::Parse(errors))
+    }
+}
+
+/// Total points, each card with matches being worth 2^(matches - 1).
+fn points(cards: &[Scratchcard]) -> Result<usize, Error> {
+    cards
+        .iter()
+        .filter(|card| card.win_count > 0)
+        .try_fold(0usize, |total, card| {
+            u32::try_from(card.win_count - 1)
+                .ok()
+                .and_then(|shift| 1usize.checked_shl(shift))
+                .and_then(|points| total.checked_add(points))
+                .ok_or(Error::TooManyPoints { card: card.id })
+        })
+}
+
+/// Cards that win copies of cards past the end of the table.
+fn overreaching(cards: &[Scratchcard]) -> Vec<Overreach> {
+    cards
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, card)| {
+            let remaining = cards.len() - idx - 1;
+            (card.win_count > remaining).then_some(Overreach {
+                card: card.id,
+                wins: card.win_count,
+                remaining,
+            })
+        })
+        .collect()
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
+fn run(args: Args) -> Result<(), Error> {
     let file = File::open(args.filename)?;
     let reader = BufReader::new(file);
 
-    let mut cards: Vec<_> = reader
-        .lines()
-        .map(|line| {
-            let line = line.unwrap();
-            let Some((_, numbers)) = line.split_once(": ") else {
-                return Scratchcard::default();
-            };
-            let Some((winning, have)) = numbers.split_once(" | ") else {
-                return Scratchcard::default();
-            };
-            let winning: HashSet<_> = winning
-                .split_whitespace()
-                .map(|n| n.parse::<usize>().unwrap())
-                .collect();
-            let win_count = have
-                .split_whitespace()
-                .filter(|&n| winning.contains(&n.parse::<usize>().unwrap()))
-                .count();
-            Scratchcard {
-                win_count,
-                card_count: 1,
-            }
+    let mut cards: Vec<Scratchcard> = read_cards(reader)?
+        .iter()
+        .map(|card| Scratchcard {
+            id: card.id,
+            win_count: card.matches(),
+            card_count: 1,
         })
         .collect();
 
-    let sum = (0..cards.len())
-        .map(|idx| {
-            let Scratchcard {
-                win_count,
-                card_count,
-            } = cards[idx];
-            for won in cards[(idx + 1)..(idx + 1 + win_count)].iter_mut() {
-                won.card_count += card_count;
-            }
-            card_count
-        })
-        .sum::<usize>();
+    let overreaching = overreaching(&cards);
+    if !overreaching.is_empty() {
+        if args.overflow == Overflow::Error {
+            return Err(Error::Overflow(overreaching));
+        }
+        for overreach in &overreaching {
+            eprintln!("Warning: {overreach}");
+        }
+    }
+
+    let len = cards.len();
+    for idx in 0..len {
+        let Scratchcard {
+            win_count,
+            card_count,
+            ..
+        } = cards[idx];
+        let won = match args.overflow {
+            Overflow::Wrap => win_count,
+            Overflow::Clamp | Overflow::Error => win_count.min(len - idx - 1),
+        };
+        for offset in 1..=won {
+            cards[(idx + offset) % len].card_count += card_count;
+        }
+    }
+    let sum: usize = cards.iter().map(|card| card.card_count).sum();
 
-    println!("{sum}");
+    match args.part {
+        Some(Part::One) => println!("{}", points(&cards)?),
+        Some(Part::Two) => println!("{sum}"),
+        None => {
+            println!("Part 1: {}", points(&cards)?);
+            println!("Part 2: {sum}");
+        }
+    }
 
     Ok(())
 }